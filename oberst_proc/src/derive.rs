@@ -0,0 +1,141 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DataEnum, DataStruct, DeriveInput, Error, Fields};
+
+use crate::take_string_attribute;
+
+/// Convert a variant name like `NotFound` to `not_found`.
+/// Runs of uppercase letters are treated as a single word, so `HTTPError` becomes `http_error`.
+fn to_snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut result = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_lowercase = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_numeric()
+                || previous.is_uppercase() && next_lowercase
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+pub(crate) fn derive_argument(input: DeriveInput) -> syn::Result<TokenStream> {
+    match &input.data {
+        Data::Enum(data) => derive_enum(&input, data),
+        Data::Struct(data) => derive_struct(&input, data),
+        Data::Union(_) => Err(Error::new_spanned(
+            &input.ident,
+            "Argument can only be derived for enums and structs",
+        )),
+    }
+}
+
+/// Parse fieldless enums from the names of their variants.
+fn derive_enum(input: &DeriveInput, data: &DataEnum) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut arms = vec![];
+    let mut keywords = vec![];
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "Argument can only be derived for enums without fields",
+            ));
+        }
+        let ident = &variant.ident;
+        let keyword = take_string_attribute(&mut variant.attrs.clone(), "rename")?
+            .unwrap_or_else(|| to_snake_case(&ident.to_string()))
+            .to_lowercase();
+        arms.push(quote! { #keyword => Ok(#name::#ident), });
+        keywords.push(keyword);
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::oberst::parser::Argument for #name #ty_generics #where_clause {
+            fn parse<'a>(
+                parser: &mut ::oberst::parser::CommandParser<'a>,
+            ) -> Result<Self, ::oberst::parser::ParseError<'a>> {
+                let start = parser.branch();
+                let keyword = parser.read_while(|c| c.is_alphanumeric() || c == '_' || c == '-');
+                match keyword.to_lowercase().as_str() {
+                    #(#arms)*
+                    _ => Err(start.error(::oberst::parser::ParseErrorKind::BadArgument)),
+                }
+            }
+
+            fn suggestions(_parser: &::oberst::parser::CommandParser) -> Vec<String> {
+                vec![#(#keywords.to_string()),*]
+            }
+        }
+    })
+}
+
+/// Parse structs from their fields in declaration order, separated by whitespace.
+fn derive_struct(input: &DeriveInput, data: &DataStruct) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    if !generics.params.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for field in &data.fields {
+            let ty = &field.ty;
+            where_clause
+                .predicates
+                .push(parse_quote! { #ty: ::oberst::parser::Argument });
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let fields = data.fields.iter().enumerate().map(|(i, field)| {
+        let ty = &field.ty;
+        let spacing = (i > 0).then(|| quote! { parser.spacing()?; });
+        let value = quote! {
+            {
+                #spacing
+                parser.argument::<#ty>()?
+            }
+        };
+        match &field.ident {
+            Some(ident) => quote! { #ident: #value },
+            None => value,
+        }
+    });
+    let construct = match &data.fields {
+        Fields::Named(_) => quote! { #name { #(#fields),* } },
+        Fields::Unnamed(_) => quote! { #name ( #(#fields),* ) },
+        Fields::Unit => quote! { #name },
+    };
+
+    // Only the first field can be suggested, as the parser is positioned at the start of the argument
+    let suggestions = match data.fields.iter().next() {
+        Some(field) => {
+            let ty = &field.ty;
+            quote! { <#ty as ::oberst::parser::Argument>::suggestions(parser) }
+        }
+        None => quote! {
+            let _ = parser;
+            Vec::new()
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::oberst::parser::Argument for #name #ty_generics #where_clause {
+            fn parse<'a>(
+                parser: &mut ::oberst::parser::CommandParser<'a>,
+            ) -> Result<Self, ::oberst::parser::ParseError<'a>> {
+                Ok(#construct)
+            }
+
+            fn suggestions(parser: &::oberst::parser::CommandParser) -> Vec<String> {
+                #suggestions
+            }
+        }
+    })
+}
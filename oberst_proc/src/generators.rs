@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, Ident, LitStr, Path, Type};
+
+use crate::take_help;
+
+/// Read the options given in an attribute like `#[settings(command = "config", context = "Context")]`.
+fn parse_options(
+    input: &DeriveInput,
+    name: &str,
+    allowed: &[&str],
+) -> syn::Result<HashMap<String, LitStr>> {
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident(name))
+        .ok_or_else(|| {
+            Error::new_spanned(
+                &input.ident,
+                format!(
+                    "Expected `#[{}(command = \"...\", context = \"...\")]`",
+                    name
+                ),
+            )
+        })?;
+    let mut options = HashMap::new();
+    attr.parse_nested_meta(|meta| {
+        let key = meta
+            .path
+            .get_ident()
+            .map(Ident::to_string)
+            .unwrap_or_default();
+        if !allowed.contains(&key.as_str()) {
+            return Err(meta.error(format!(
+                "Unknown option, expected one of {}",
+                allowed.join(", ")
+            )));
+        }
+        options.insert(key, meta.value()?.parse::<LitStr>()?);
+        Ok(())
+    })?;
+    for required in ["command", "context"] {
+        if !options.contains_key(required) {
+            return Err(Error::new_spanned(
+                attr,
+                format!("Missing option `{}`", required),
+            ));
+        }
+    }
+    Ok(options)
+}
+
+/// The options shared by all generated command families.
+struct FamilyOptions {
+    command: Ident,
+    context: Type,
+    /// The fields leading from the context to the value the commands operate on, e.g. `settings.audio`.
+    path: Vec<Ident>,
+    /// A function writing text produced by the commands, taking the context and the text.
+    output: Option<Path>,
+}
+
+impl FamilyOptions {
+    fn new(options: &HashMap<String, LitStr>) -> syn::Result<Self> {
+        let path = match options.get("path") {
+            Some(path) => path
+                .value()
+                .split('.')
+                .map(|field| {
+                    syn::parse_str::<Ident>(field).map_err(|_| {
+                        Error::new(path.span(), "Expected a path like `settings.audio`")
+                    })
+                })
+                .collect::<syn::Result<_>>()?,
+            None => vec![],
+        };
+        Ok(Self {
+            command: options["command"].parse()?,
+            context: options["context"].parse()?,
+            path,
+            output: options.get("output").map(LitStr::parse).transpose()?,
+        })
+    }
+
+    /// An expression accessing the value the commands operate on through the context,
+    /// which is bound to `__context` so that it can't clash with the names of the struct's fields.
+    fn target(&self) -> TokenStream {
+        let path = &self.path;
+        quote! { __context #(.#path)* }
+    }
+
+    /// Statements writing `text` to the output.
+    fn write(&self) -> TokenStream {
+        match &self.output {
+            Some(output) => quote! { #output(__context, &text); },
+            None => quote! { ::oberst::write_output(&text); },
+        }
+    }
+}
+
+/// Generate `get <field>`, `set <field> <value>` and `list` variants for the fields of a settings struct.
+pub(crate) fn derive_settings(input: DeriveInput) -> syn::Result<TokenStream> {
+    let options = FamilyOptions::new(&parse_options(
+        &input,
+        "settings",
+        &["command", "context", "path", "output"],
+    )?)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "Settings can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Settings can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    let FamilyOptions {
+        command, context, ..
+    } = &options;
+    let target = options.target();
+    let write = options.write();
+    let mut variants = vec![];
+    let mut lines = vec![];
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named fields have identifiers");
+        let ty = &field.ty;
+        let name = ident.to_string();
+        let get = format_ident!("get_{}", ident);
+        let set = format_ident!("set_{}", ident);
+        let get_args = format!("get {}", name);
+        let set_args = format!("set {} <value>", name);
+        let help = take_help(&mut field.attrs.clone())?.map(|help| quote! { #[help = #help] });
+        lines.push(quote! { format!("{} = {}", #name, #target.#ident) });
+        variants.push(quote! {
+            #[args = #get_args]
+            fn #get(__context: &#context) {
+                let text = format!("{} = {}", #name, #target.#ident);
+                #write
+            }
+
+            #[args = #set_args]
+            fn #set(__context: &mut #context, #help value: #ty) {
+                #target.#ident = value;
+            }
+        });
+    }
+
+    Ok(quote! {
+        ::oberst::define_command! {#command (#context) {
+            #(#variants)*
+
+            #[args = "list"]
+            fn list(__context: &#context) {
+                let text = [#(#lines),*].join("\n");
+                #write
+            }
+        }}
+    })
+}
+
+/// Generate `add <name> <fields...>`, `remove <name>`, `list` and `info <name>` variants
+/// for a map from names to items of the struct held in the context.
+pub(crate) fn derive_collection(input: DeriveInput) -> syn::Result<TokenStream> {
+    let raw = parse_options(
+        &input,
+        "collection",
+        &["command", "context", "path", "key", "validate", "output"],
+    )?;
+    let options = FamilyOptions::new(&raw)?;
+    let key: Type = match raw.get("key") {
+        Some(key) => key.parse()?,
+        None => syn::parse_quote! { String },
+    };
+    let validate = raw.get("validate").map(LitStr::parse::<Path>).transpose()?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "Collection can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Collection can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    let item = &input.ident;
+    let FamilyOptions {
+        command, context, ..
+    } = &options;
+    let target = options.target();
+    let write = options.write();
+    let idents = fields
+        .iter()
+        .map(|field| field.ident.as_ref().expect("named fields have identifiers"))
+        .collect::<Vec<_>>();
+    if let Some(ident) = idents.iter().find(|ident| **ident == "name") {
+        return Err(Error::new_spanned(
+            ident,
+            "The field `name` conflicts with the `<name>` argument of `add`",
+        ));
+    }
+    let parameters = fields
+        .iter()
+        .map(|field| {
+            let ident = &field.ident;
+            let ty = &field.ty;
+            let help = take_help(&mut field.attrs.clone())?.map(|help| quote! { #[help = #help] });
+            Ok(quote! { #help #ident: #ty })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let add_args = idents
+        .iter()
+        .fold(String::from("add <name>"), |args, ident| {
+            format!("{} <{}>", args, ident)
+        });
+    let names = idents.iter().map(|ident| ident.to_string());
+    let validate = validate.map(|validate| {
+        quote! {
+            #validate(&*__context, &name, &__item)
+                .map_err(::oberst::CommandError::dispatch)?;
+        }
+    });
+    let missing = quote! {
+        ::oberst::CommandError::dispatch(format!("There is no {} named `{}`", stringify!(#command), name))
+    };
+
+    Ok(quote! {
+        ::oberst::define_command! {#command (#context) {
+            #[args = #add_args]
+            fn add(__context: &mut #context, name: #key, #(#parameters),*) -> ::oberst::CommandResult<'static> {
+                if #target.contains_key(&name) {
+                    return Err(::oberst::CommandError::dispatch(
+                        format!("There already is a {} named `{}`", stringify!(#command), name),
+                    ));
+                }
+                let __item = #item { #(#idents),* };
+                #validate
+                #target.insert(name, __item);
+                Ok(0)
+            }
+
+            #[args = "remove <name>"]
+            fn remove(__context: &mut #context, name: #key) -> ::oberst::CommandResult<'static> {
+                match #target.remove(&name) {
+                    Some(_) => Ok(0),
+                    None => Err(#missing),
+                }
+            }
+
+            #[args = "list"]
+            fn list(__context: &#context) {
+                let mut names = #target.keys().map(|name| name.to_string()).collect::<Vec<_>>();
+                names.sort();
+                let text = names.join(", ");
+                #write
+            }
+
+            #[args = "info <name>"]
+            fn info(__context: &#context, name: #key) -> ::oberst::CommandResult<'static> {
+                let item = #target.get(&name).ok_or_else(|| #missing)?;
+                let text = [#(format!("{} = {}", #names, item.#idents)),*].join(", ");
+                #write
+                Ok(0)
+            }
+        }}
+    })
+}
@@ -1,55 +1,104 @@
-use std::collections::HashMap;
-
-use proc_macro::{Span, TokenStream};
-use quote::{quote, ToTokens};
+use proc_macro::TokenStream;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
-    braced, parenthesized, parse_macro_input, parse_quote, spanned::Spanned, Attribute, Error,
-    FnArg, Ident, ItemFn, Pat, PatType, Signature, Type, TypeReference,
+    braced, parenthesized, parse_macro_input, parse_quote, punctuated::Punctuated,
+    spanned::Spanned, Attribute, Error, FnArg, Ident, ItemFn, LitStr, Pat, PatType, Signature,
+    Token, Type, TypeReference,
 };
 
+mod derive;
+mod generators;
+
 /// Define a command with the given name and context type.
 /// Commands are defined as functions that take a reference to their context type as their first argument.
+/// Variants taking a mutable reference to the context have to be run using `CommandSource::dispatch_mut`.
 /// The function should return a `Result` with an `i32` as the success value or `()`.
 /// By default, a command's usage is generated from its arguments in sequential order.
 /// To specify a custom usage string, add an `args` attribute to the function.
 /// The attribute should be a string literal containing the desired usage string **excluding** the command name.
+/// Parameters can be annotated with `#[rename = "..."]` to change the name they are displayed with,
+/// and with `#[help = "..."]` or doc comments to describe them in the command's help text.
+/// Trailing parameters of type `Option<T>` are optional and receive `None` if the command ends before them.
+/// A parameter of type `Vec<T>` takes all remaining values and has to be the last one, as does `GreedyString`.
+/// Annotated with `#[greedy]` or `#[lazy]`, a `Vec<T>` may be followed by further syntax instead and takes as many
+/// or as few values as the rest of the command allows, possibly none. These attributes only apply to `Vec<T>`,
+/// as other arguments such as `String` or `Word` always take a single value.
+/// `String` parameters annotated with `#[literals = "..."]` only accept values of the literal set with the given name,
+/// see `CommandSource::register_literals`.
+/// Numeric parameters can be annotated with `#[radix = 16]` and `#[digit_separator = "_"]` to parse them in a different notation;
+/// without a `digit_separator`, the one set in `ParserConfig::digit_separator` is used.
+/// With the `async` feature of `oberst` enabled, variants can be `async fn`s, which have to be run using `CommandSource::dispatch_async`.
+/// Aliases for the command can be given by adding `#[alias("...", ...)]` in front of its name;
+/// they are registered alongside the command by `register_command!`.
+/// Commands, variants and parameters are given localization keys like `commands.<command>.<variant>.<parameter>`,
+/// which can be overridden using `#[key = "..."]` in front of the command's name, a function or a parameter.
+/// The parser of each variant is available as `<command>::parse_variant_<index>`, in the order the functions are defined.
 #[proc_macro]
 pub fn define_command(input: TokenStream) -> TokenStream {
     let CommandDefiniton {
         name,
+        key,
+        aliases,
         context_type,
         variants,
     } = parse_macro_input!(input as CommandDefiniton);
 
     let functions = variants.iter().map(|variant| &variant.function);
 
-    let dispatchers = variants.iter().map(|variant| {
+    let parser_names = (0..variants.len())
+        .map(|i| format_ident!("parse_variant_{}", i))
+        .collect::<Vec<_>>();
+    let parsers = variants.iter().zip(&parser_names).map(|(variant, parser_name)| {
         let parser = variant.generate_parser();
+        let doc = format!(
+            "Parse the arguments of the variant `{}` following the command's name, e.g. to reuse it in custom dispatch logic.",
+            variant.function.sig.ident
+        );
 
         quote! {
-            CommandDispatch {
-                parser: #parser,
+            #[doc = #doc]
+            pub fn #parser_name<'a>(
+                parser: &mut ::oberst::parser::CommandParser<'a>,
+            ) -> Result<::oberst::Execute<'a, #context_type>, ::oberst::parser::ParseError<'a>> {
+                let parse: ::oberst::Parse<#context_type> = #parser;
+                parse(parser)
             }
         }
     });
 
     let usages = variants.iter().map(|variant| &variant.usage);
+    let variant_usages = variants.iter().map(CommandVariant::generate_usage);
+
+    let variant_count = variants.len();
 
     let result = quote! {
         mod #name {
             use super::*;
-            pub static DISPATCHERS: &[CommandDispatch<#context_type>] = &[
-                #(#dispatchers),*
+            pub static DISPATCHERS: &[::oberst::CommandDispatch<#context_type>; #variant_count] = &[
+                #(::oberst::CommandDispatch { parser: #parser_names }),*
+            ];
+
+            pub static SPEC: ::oberst::CommandSpec<#context_type, #variant_count> =
+                ::oberst::CommandSpec::new(stringify!(#name), &USAGE, DISPATCHERS);
+
+            #(#parsers)*
+
+            pub static ALIASES: &[&str] = &[
+                #(#aliases),*
             ];
 
-            pub static USAGE: CommandUsage = CommandUsage {
+            pub static USAGE: ::oberst::CommandUsage = ::oberst::CommandUsage {
                 name: stringify!(#name),
+                key: #key,
                 usage: &[
                     #(
                         #usages,
                     )*
                 ],
                 description: None,
+                variants: &[
+                    #(#variant_usages),*
+                ],
             };
 
             #(#functions)*
@@ -59,15 +108,70 @@ pub fn define_command(input: TokenStream) -> TokenStream {
     result.into()
 }
 
+/// Derive `Argument` for an enum without fields or a struct.
+/// Each variant of an enum is parsed from its name in snake case, ignoring case, e.g. `NotFound` from `not_found`.
+/// Use `#[rename = "..."]` on a variant to accept a different name instead.
+/// The fields of a struct are parsed in declaration order, separated by whitespace.
+#[proc_macro_derive(Argument, attributes(rename))]
+pub fn derive_argument(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    derive::derive_argument(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive a command family reading and changing the fields of a settings struct held in the context:
+/// `get <field>` writes a field's value, `set <field> <value>` replaces it and `list` writes all fields.
+/// The struct is configured with `#[settings(command = "config", context = "Context", path = "settings")]`,
+/// where `path` leads from the context to the struct through its fields and may be omitted if the context is the struct itself.
+/// Values are written to the output of the source using `oberst::write_output`, or the function given as `output = "..."` taking the context and the text.
+/// Each field has to implement `Argument` and `Display`; its doc comment is used as the help text of its value.
+/// As `set` changes the context, it has to be run using `CommandSource::dispatch_mut`.
+/// The command is registered using `register_command!` like any other.
+#[proc_macro_derive(Settings, attributes(settings))]
+pub fn derive_settings(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    generators::derive_settings(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive a command family managing items of a struct stored by name in a map held in the context, e.g. warps:
+/// `add <name> <fields...>` adds an item, `remove <name>` removes one, `list` writes the names of all items
+/// and `info <name>` writes the fields of an item.
+/// The struct is configured with `#[collection(command = "warp", context = "Context", path = "warps")]`,
+/// where `path` leads from the context to a `HashMap` or `BTreeMap` through its fields.
+/// The names are parsed as `String` unless another type is given as `key = "..."`, e.g. `key = "Word"`.
+/// New items can be checked using `validate = "..."`, a function taking the context, the name and the item
+/// and returning a `Result` whose error is converted into a `CommandError::Dispatch`.
+/// Text is written to the output of the source using `oberst::write_output`, or the function given as `output = "..."` taking the context and the text.
+/// Each field has to implement `Argument` and `Display`; its doc comment is used as its help text.
+/// Fields may not be called `name`, which is the argument the items are named by.
+/// As `add` and `remove` change the context, they have to be run using `CommandSource::dispatch_mut`.
+#[proc_macro_derive(Collection, attributes(collection))]
+pub fn derive_collection(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    generators::derive_collection(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
 struct CommandDefiniton {
     name: Ident,
+    /// The localization key of the command.
+    key: String,
+    aliases: Vec<String>,
     context_type: Type,
     variants: Vec<CommandVariant>,
 }
 
 impl syn::parse::Parse for CommandDefiniton {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let name = input.parse()?;
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let key = take_string_attribute(&mut attrs, "key")?;
+        let aliases = parse_aliases(attrs)?;
+        let name: Ident = input.parse()?;
+        let key = key.unwrap_or_else(|| format!("commands.{}", name));
         let context_type;
         parenthesized!(context_type in input);
         let context_type: Type = context_type.parse()?;
@@ -77,47 +181,170 @@ impl syn::parse::Parse for CommandDefiniton {
         let mut variants = vec![];
         while !variant_block.is_empty() {
             let mut function = variant_block.parse::<syn::ItemFn>()?;
-            check_context_arg(&function.sig, &context_type)?;
-            let arg_names = extract_args_from_signature(&function.sig)?;
+            let mutable = check_context_arg(&function.sig, &context_type)?;
+            if mutable && function.sig.asyncness.is_some() {
+                return Err(Error::new(
+                    function.sig.span(),
+                    "Async variants can't take a mutable reference to the context",
+                ));
+            }
+            let variant_key = take_string_attribute(&mut function.attrs, "key")?
+                .unwrap_or_else(|| format!("{}.{}", key, function.sig.ident));
+            let arguments = extract_args_from_signature(&mut function.sig)?;
 
             let syntax =
                 if let Some(usage) = extract_usage_string_from_metadata(&mut function.attrs)? {
-                    build_syntax_from_usage(&arg_names, usage)
+                    build_syntax_from_usage(&arguments, usage, &function)?
                 } else {
-                    build_syntax_from_signature(&arg_names)
+                    build_syntax_from_signature(&arguments)
                 };
+            check_optional_arguments(&syntax, &function)?;
+            check_final_arguments(&syntax)?;
 
             variants.push(CommandVariant {
                 function,
+                key: variant_key,
+                mutable,
                 usage: build_usage_string(&syntax),
                 syntax,
             });
         }
         Ok(Self {
             name,
+            key,
+            aliases,
             context_type,
             variants,
         })
     }
 }
 
+/// Collect the aliases given by `#[alias("...", ...)]` attributes on the command.
+fn parse_aliases(attrs: Vec<Attribute>) -> syn::Result<Vec<String>> {
+    let mut aliases = vec![];
+    for attr in attrs {
+        if !attr.path().is_ident("alias") {
+            return Err(Error::new(attr.span(), "Unknown attribute"));
+        }
+        for alias in attr.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)? {
+            let value = alias.value();
+            // The same names `CommandSource::register_alias` accepts
+            if value.is_empty() || value.contains(char::is_whitespace) {
+                return Err(Error::new(
+                    alias.span(),
+                    "Aliases may not be empty or contain whitespace",
+                ));
+            }
+            aliases.push(value);
+        }
+    }
+    Ok(aliases)
+}
+
 struct CommandVariant {
     function: ItemFn,
+    /// The localization key of the variant.
+    key: String,
+    /// Whether the variant takes a mutable reference to the context.
+    mutable: bool,
     usage: String,
     syntax: Vec<CommandSyntax>,
 }
 
 impl CommandVariant {
+    fn generate_usage(&self) -> proc_macro2::TokenStream {
+        let usage = &self.usage;
+        let variant_key = &self.key;
+        let syntax = self.syntax.iter().map(|syntax| match syntax {
+            CommandSyntax::Literal(literal) => quote! {
+                ::oberst::SyntaxNode::Literal(#literal)
+            },
+            CommandSyntax::Argument(
+                argument @ CommandArgument {
+                    ident,
+                    ty,
+                    name,
+                    key,
+                    help,
+                    optional,
+                    literals,
+                    ..
+                },
+            ) => {
+                let key = key
+                    .clone()
+                    .unwrap_or_else(|| format!("{}.{}", self.key, ident));
+                let ty_name = quote! { #ty }.to_string();
+                let parse = argument.generate_parse();
+                let suggest = match literals {
+                    Some(literals) => quote! { |parser| parser.literals(#literals) },
+                    None => quote! { <#ty as ::oberst::parser::Argument>::suggestions },
+                };
+                let help = match help {
+                    Some(help) => quote! { Some(#help) },
+                    None => quote! { None },
+                };
+                quote! {
+                    ::oberst::SyntaxNode::Argument(::oberst::ArgumentUsage {
+                        name: #name,
+                        key: #key,
+                        ty: #ty_name,
+                        help: #help,
+                        optional: #optional,
+                        parse: |parser| #parse.map(drop),
+                        suggest: #suggest,
+                    })
+                }
+            }
+        });
+
+        // Span `line!()` to the function so it reports the line of the variant rather than that of the macro invocation
+        let line = quote_spanned! { self.function.sig.ident.span() => line!() };
+
+        quote! {
+            ::oberst::VariantUsage {
+                usage: #usage,
+                key: #variant_key,
+                syntax: &[
+                    #(#syntax),*
+                ],
+                location: ::oberst::SourceLocation {
+                    module_path: module_path!(),
+                    file: file!(),
+                    line: #line,
+                },
+            }
+        }
+    }
+
     fn generate_caller(&self) -> syn::Expr {
         let args = self.syntax.iter().filter_map(|syntax| match syntax {
             CommandSyntax::Literal(_) => None,
-            CommandSyntax::Argument(name, _) => Some(name),
+            CommandSyntax::Argument(argument) => Some(&argument.ident),
         });
 
         let return_type = &self.function.sig.output;
         let name = &self.function.sig.ident;
 
-        let call: syn::Block = if let syn::ReturnType::Default = return_type {
+        let unit = matches!(return_type, syn::ReturnType::Default);
+
+        if self.function.sig.asyncness.is_some() {
+            let call = if unit {
+                quote! {
+                    #name(ctx, #(#args,)*).await;
+                    Ok(0)
+                }
+            } else {
+                quote! { #name(ctx, #(#args,)*).await }
+            };
+            return parse_quote! {
+                Ok(::oberst::Execute::from_async(move |ctx| Box::pin(async move {
+                    #call
+                })))
+            };
+        }
+
+        let call: syn::Block = if unit {
             parse_quote! { {
                 #name(ctx, #(#args,)*);
                 Ok(0)
@@ -129,33 +356,27 @@ impl CommandVariant {
             }
         };
 
-        parse_quote! {
-            Ok(Box::new(move |ctx| {
-                #call
-            }))
+        if self.mutable {
+            parse_quote! {
+                Ok(::oberst::Execute::Mut(Box::new(move |ctx| {
+                    #call
+                })))
+            }
+        } else {
+            parse_quote! {
+                Ok(::oberst::Execute::Sync(Box::new(move |ctx| {
+                    #call
+                })))
+            }
         }
     }
 
     fn generate_parser(&self) -> syn::Expr {
-        let parser = self.syntax.iter().map(|syntax| match syntax {
-            CommandSyntax::Literal(literal) => {
-                quote! {
-                    parser.spacing()?;
-                    parser.lit(#literal)?;
-                }
-            }
-            CommandSyntax::Argument(name, ty) => {
-                quote! {
-                    parser.spacing()?;
-                    let #name = parser.argument::<#ty>()?;
-                }
-            }
-        });
-
+        let parser = generate_steps(&self.syntax);
         let caller = self.generate_caller();
         parse_quote! {
             |parser| {
-                #(#parser)*
+                #parser
                 parser.end()?;
                 #caller
 
@@ -166,7 +387,189 @@ impl CommandVariant {
 
 enum CommandSyntax {
     Literal(String),
-    Argument(Ident, Type),
+    Argument(CommandArgument),
+}
+
+/// A parameter of a command variant.
+#[derive(Clone)]
+struct CommandArgument {
+    ident: Ident,
+    ty: Type,
+    /// The name shown to users, which defaults to the identifier but can be changed using `#[rename = "..."]`.
+    name: String,
+    /// The localization key given by `#[key = "..."]`, derived from the variant's key otherwise.
+    key: Option<String>,
+    /// Help text given by a `#[help = "..."]` attribute or doc comments.
+    help: Option<String>,
+    /// Whether the parameter is an `Option`, in which case `ty` is the type of its value.
+    optional: bool,
+    /// The name of the literal set given by `#[literals = "..."]`.
+    literals: Option<String>,
+    /// The notation given by `#[radix = ...]` and `#[digit_separator = "..."]` for numeric arguments.
+    format: Option<NumberFormat>,
+    /// How many values a `Vec<T>` followed by further syntax takes, given by `#[greedy]` or `#[lazy]`.
+    matching: Option<Matching>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Matching {
+    /// Take as many values as possible while still allowing the rest of the command to be parsed.
+    Greedy,
+    /// Take as few values as possible while still allowing the rest of the command to be parsed.
+    Lazy,
+}
+
+#[derive(Clone, Copy)]
+struct NumberFormat {
+    radix: u32,
+    separator: Option<char>,
+}
+
+impl CommandArgument {
+    /// Generate an expression parsing this argument from `parser`.
+    fn generate_parse(&self) -> proc_macro2::TokenStream {
+        let ty = &self.ty;
+        if let Some(literals) = &self.literals {
+            return quote! { parser.dynamic_lit(#literals) };
+        }
+        match self.format {
+            Some(NumberFormat { radix, separator }) => {
+                let separator = match separator {
+                    Some(separator) => quote! { Some(#separator) },
+                    None => quote! { parser.config().digit_separator },
+                };
+                quote! {
+                    <#ty as ::oberst::parser::Number>::parse_with(parser, ::oberst::parser::NumberFormat {
+                        radix: #radix,
+                        separator: #separator,
+                    })
+                }
+            }
+            None => quote! { parser.argument::<#ty>() },
+        }
+    }
+
+    /// The type of the function parameter this argument is passed as.
+    fn parameter_type(&self) -> proc_macro2::TokenStream {
+        let ty = &self.ty;
+        if self.optional {
+            quote! { Option<#ty> }
+        } else {
+            quote! { #ty }
+        }
+    }
+}
+
+/// Generate the statements parsing the given syntax from `parser`, binding each argument to its identifier.
+fn generate_steps(syntax: &[CommandSyntax]) -> proc_macro2::TokenStream {
+    let mut steps = vec![];
+    for (i, node) in syntax.iter().enumerate() {
+        match node {
+            CommandSyntax::Literal(literal) => steps.push(quote! {
+                parser.spacing()?;
+                parser.lit(#literal)?;
+            }),
+            CommandSyntax::Argument(argument) if argument.matching.is_some() => {
+                steps.push(generate_backtracking(argument, &syntax[i + 1..]));
+                break;
+            }
+            CommandSyntax::Argument(argument) => {
+                let ident = &argument.ident;
+                let parse = argument.generate_parse();
+                steps.push(if argument.optional {
+                    quote! {
+                        let #ident = {
+                            let mut rest = parser.branch();
+                            rest.read_while(char::is_whitespace);
+                            if rest.end().is_ok() {
+                                *parser = rest;
+                                None
+                            } else {
+                                parser.spacing()?;
+                                Some(#parse?)
+                            }
+                        };
+                    }
+                } else {
+                    quote! {
+                        parser.spacing()?;
+                        let #ident = #parse?;
+                    }
+                });
+            }
+        }
+    }
+    quote! { #(#steps)* }
+}
+
+/// Generate the statements parsing a `Vec<T>` annotated with `#[greedy]` or `#[lazy]` along with the syntax following it.
+/// Each possible number of values, including none, is tried in order until the rest of the command can be parsed.
+fn generate_backtracking(
+    argument: &CommandArgument,
+    rest: &[CommandSyntax],
+) -> proc_macro2::TokenStream {
+    let ident = &argument.ident;
+    let ty = &argument.ty;
+    let item = generic_argument(ty, "Vec").expect("checked when extracting the argument");
+    let arguments = rest
+        .iter()
+        .filter_map(|syntax| match syntax {
+            CommandSyntax::Argument(argument) => Some(argument),
+            CommandSyntax::Literal(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let idents = arguments.iter().map(|argument| &argument.ident);
+    let types = arguments.iter().map(|argument| argument.parameter_type());
+    let steps = generate_steps(rest);
+    let counts = match argument.matching {
+        Some(Matching::Lazy) => quote! { (0..=max).collect::<Vec<_>>() },
+        _ => quote! { (0..=max).rev().collect::<Vec<_>>() },
+    };
+    let bindings = std::iter::once(ident).chain(arguments.iter().map(|argument| &argument.ident));
+
+    quote! {
+        let (#(#bindings,)*) = {
+            fn parse_rest<'a>(
+                parser: &mut ::oberst::parser::CommandParser<'a>,
+                count: usize,
+            ) -> Result<(#ty, #(#types,)*), ::oberst::parser::ParseError<'a>> {
+                let mut #ident = Vec::with_capacity(count);
+                for _ in 0..count {
+                    parser.spacing()?;
+                    #ident.push(parser.argument::<#item>()?);
+                }
+                #steps
+                parser.end()?;
+                Ok((#ident, #(#idents,)*))
+            }
+
+            // Count the values available, then find the first count the rest of the command can be parsed with
+            let mut max = 0;
+            let mut values = parser.branch();
+            while values.spacing().is_ok() && values.argument::<#item>().is_ok() {
+                max += 1;
+            }
+            let mut parsed = None;
+            let mut furthest = None;
+            for count in #counts {
+                let mut attempt = parser.branch();
+                match parse_rest(&mut attempt, count) {
+                    Ok(values) => {
+                        *parser = attempt;
+                        parsed = Some(values);
+                        break;
+                    }
+                    // Taking every available value gets the furthest, so its error is the most helpful
+                    Err(error) if count == max => furthest = Some(error),
+                    Err(_) => {}
+                }
+            }
+            match parsed {
+                Some(values) => values,
+                None => return Err(furthest.expect("every count up to `max` is tried")),
+            }
+        };
+    }
 }
 
 fn build_usage_string(syntax: &[CommandSyntax]) -> String {
@@ -174,98 +577,293 @@ fn build_usage_string(syntax: &[CommandSyntax]) -> String {
         .iter()
         .map(|s| match s {
             CommandSyntax::Literal(lit) => lit.to_string(),
-            CommandSyntax::Argument(name, ty) => format!("<{}: {}>", name, quote! { #ty }),
+            CommandSyntax::Argument(CommandArgument {
+                name, ty, optional, ..
+            }) => {
+                if *optional {
+                    format!("[<{}: {}>]", name, quote! { #ty })
+                } else {
+                    format!("<{}: {}>", name, quote! { #ty })
+                }
+            }
         })
         .collect::<Vec<_>>()
         .join(" ")
 }
 
-fn extract_args_from_signature(sig: &Signature) -> syn::Result<HashMap<Ident, Type>> {
+fn extract_args_from_signature(sig: &mut Signature) -> syn::Result<Vec<CommandArgument>> {
     sig.inputs
-        .iter()
+        .iter_mut()
         .skip(1)
         .map(|arg| {
             if let FnArg::Typed(pat) = arg {
                 if let Pat::Ident(ident) = &*pat.pat {
-                    Ok((ident.ident.clone(), *pat.ty.clone()))
+                    let name = take_string_attribute(&mut pat.attrs, "rename")?
+                        .unwrap_or_else(|| ident.ident.to_string());
+                    let key = take_string_attribute(&mut pat.attrs, "key")?;
+                    let help = take_help(&mut pat.attrs)?;
+                    let literals = take_string_attribute(&mut pat.attrs, "literals")?;
+                    let format = take_number_format(&mut pat.attrs)?;
+                    let matching = take_matching(&mut pat.attrs)?;
+                    let (ty, optional) = match option_value_type(&pat.ty) {
+                        Some(ty) => (ty.clone(), true),
+                        None => (*pat.ty.clone(), false),
+                    };
+                    if matching.is_some() && (optional || generic_argument(&ty, "Vec").is_none()) {
+                        return Err(Error::new(
+                            pat.ty.span(),
+                            "`#[greedy]` and `#[lazy]` can only be applied to `Vec<T>` parameters",
+                        ));
+                    }
+                    Ok(CommandArgument {
+                        ident: ident.ident.clone(),
+                        ty,
+                        name,
+                        key,
+                        help,
+                        optional,
+                        literals,
+                        format,
+                        matching,
+                    })
                 } else {
-                    return Err(Error::new(pat.pat.span(), "Expected identifier"));
+                    Err(Error::new(pat.pat.span(), "Expected identifier"))
                 }
             } else {
-                return Err(Error::new(arg.span(), "Expected typed argument"));
+                Err(Error::new(arg.span(), "Expected typed argument"))
             }
         })
-        .collect::<syn::Result<HashMap<_, _>>>()
+        .collect()
 }
 
-fn extract_usage_string_from_metadata(attrs: &mut Vec<Attribute>) -> syn::Result<Option<String>> {
-    let mut usage = None;
-
-    for (i, attr) in attrs.iter().enumerate() {
-        if attr.path().is_ident("args") {
-            match &attr.meta {
-                syn::Meta::NameValue(syn::MetaNameValue {
-                    value:
-                        syn::Expr::Lit(syn::ExprLit {
-                            lit: syn::Lit::Str(lit),
-                            ..
-                        }),
+/// Get the type `T` if the given type is `Option<T>`.
+fn option_value_type(ty: &Type) -> Option<&Type> {
+    generic_argument(ty, "Option")
+}
+
+/// Get the type `T` if the given type is `<name><T>`, e.g. `Vec<T>`.
+fn generic_argument<'t>(ty: &'t Type, name: &str) -> Option<&'t Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first()? {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Check that optional arguments are only followed by further optional arguments.
+fn check_optional_arguments(syntax: &[CommandSyntax], function: &ItemFn) -> syn::Result<()> {
+    let first_optional = syntax.iter().position(
+        |syntax| matches!(syntax, CommandSyntax::Argument(argument) if argument.optional),
+    );
+    let Some(first_optional) = first_optional else {
+        return Ok(());
+    };
+    if syntax[first_optional..]
+        .iter()
+        .all(|syntax| matches!(syntax, CommandSyntax::Argument(argument) if argument.optional))
+    {
+        Ok(())
+    } else {
+        Err(Error::new(
+            function.sig.ident.span(),
+            "Optional arguments may only be followed by other optional arguments",
+        ))
+    }
+}
+
+/// Whether arguments of the given type consume the rest of the command.
+fn consumes_rest(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Vec" || segment.ident == "GreedyString"),
+        _ => false,
+    }
+}
+
+/// Check that arguments consuming the rest of the command, such as `Vec<T>`, are not followed by anything else,
+/// unless they are annotated with `#[greedy]` or `#[lazy]`.
+fn check_final_arguments(syntax: &[CommandSyntax]) -> syn::Result<()> {
+    let Some((_, preceding)) = syntax.split_last() else {
+        return Ok(());
+    };
+    for syntax in preceding {
+        if let CommandSyntax::Argument(argument) = syntax {
+            if consumes_rest(&argument.ty) && argument.matching.is_none() {
+                return Err(Error::new(
+                    argument.ty.span(),
+                    format!("`{}` has to be the last argument", argument.name),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Remove the attribute `#[<name> = "..."]` from the list and return its value.
+pub(crate) fn take_string_attribute(
+    attrs: &mut Vec<Attribute>,
+    name: &str,
+) -> syn::Result<Option<String>> {
+    let Some(i) = attrs.iter().position(|attr| attr.path().is_ident(name)) else {
+        return Ok(None);
+    };
+    match &attrs.remove(i).meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            value:
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(lit),
                     ..
-                }) => {
-                    usage = Some(lit.value());
-                    attrs.remove(i);
-                    break;
+                }),
+            ..
+        }) => Ok(Some(lit.value())),
+        meta => Err(Error::new(
+            meta.span(),
+            format!("Expected `#[{} = \"...\"]`", name),
+        )),
+    }
+}
+
+/// Remove `#[help = "..."]` and doc comment attributes from the list and return the help text they contain.
+/// An explicit `help` attribute takes precedence over doc comments.
+pub(crate) fn take_help(attrs: &mut Vec<Attribute>) -> syn::Result<Option<String>> {
+    let help = take_string_attribute(attrs, "help")?;
+    let mut docs = vec![];
+    while let Some(doc) = take_string_attribute(attrs, "doc")? {
+        docs.push(doc.trim().to_string());
+    }
+    Ok(help.or_else(|| (!docs.is_empty()).then(|| docs.join(" "))))
+}
+
+/// Remove `#[radix = ...]` and `#[digit_separator = "..."]` attributes from the list and return the number format they describe.
+fn take_number_format(attrs: &mut Vec<Attribute>) -> syn::Result<Option<NumberFormat>> {
+    let radix = match attrs.iter().position(|attr| attr.path().is_ident("radix")) {
+        Some(i) => match &attrs.remove(i).meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(lit),
+                        ..
+                    }),
+                ..
+            }) => {
+                let radix = lit.base10_parse::<u32>()?;
+                if !(2..=36).contains(&radix) {
+                    return Err(Error::new(lit.span(), "The radix must be between 2 and 36"));
                 }
+                Some(radix)
+            }
+            meta => return Err(Error::new(meta.span(), "Expected `#[radix = ...]`")),
+        },
+        None => None,
+    };
+    let separator = match take_string_attribute(attrs, "digit_separator")? {
+        Some(separator) => {
+            let mut chars = separator.chars();
+            match (chars.next(), chars.next()) {
+                (Some(separator), None) => Some(separator),
                 _ => {
-                    return Err(Error::new(attr.span(), "Expected usage string"));
+                    return Err(Error::new(
+                        proc_macro2::Span::call_site(),
+                        "The digit separator must be a single character",
+                    ))
                 }
             }
         }
+        None => None,
+    };
+    Ok(
+        (radix.is_some() || separator.is_some()).then(|| NumberFormat {
+            radix: radix.unwrap_or(10),
+            separator,
+        }),
+    )
+}
+
+/// Remove a `#[greedy]` or `#[lazy]` attribute from the list and return the matching it describes.
+fn take_matching(attrs: &mut Vec<Attribute>) -> syn::Result<Option<Matching>> {
+    let mut matching = None;
+    for (name, value) in [("greedy", Matching::Greedy), ("lazy", Matching::Lazy)] {
+        if let Some(i) = attrs.iter().position(|attr| attr.path().is_ident(name)) {
+            let attr = attrs.remove(i);
+            attr.meta.require_path_only()?;
+            if matching.replace(value).is_some() {
+                return Err(Error::new(
+                    attr.span(),
+                    "`#[greedy]` and `#[lazy]` cannot be combined",
+                ));
+            }
+        }
     }
-    Ok(usage)
+    Ok(matching)
+}
+
+fn extract_usage_string_from_metadata(attrs: &mut Vec<Attribute>) -> syn::Result<Option<String>> {
+    take_string_attribute(attrs, "args")
 }
 
-fn build_syntax_from_signature(arg_names: &HashMap<Ident, Type>) -> Vec<CommandSyntax> {
-    arg_names
+fn build_syntax_from_signature(arguments: &[CommandArgument]) -> Vec<CommandSyntax> {
+    arguments
         .iter()
-        .map(|(name, ty)| CommandSyntax::Argument(name.clone(), ty.clone()))
+        .cloned()
+        .map(CommandSyntax::Argument)
         .collect()
 }
 
-fn build_syntax_from_usage(arg_names: &HashMap<Ident, Type>, usage: String) -> Vec<CommandSyntax> {
-    return usage
-        .split(" ")
-        .into_iter()
+/// Build the syntax described by an `args` attribute.
+/// Arguments may be referred to by either their display name or their identifier.
+fn build_syntax_from_usage(
+    arguments: &[CommandArgument],
+    usage: String,
+    function: &ItemFn,
+) -> syn::Result<Vec<CommandSyntax>> {
+    usage
+        .split(' ')
         .map(|segment| {
-            if segment.starts_with("<") {
-                let name = segment
-                    .chars()
-                    .skip(1)
-                    .take_while(|c| *c != '>')
-                    .collect::<String>();
-                let name_ident = Ident::new(&name, Span::call_site().into());
-                let ty = arg_names.get(&name_ident);
-                if let Some(ty) = ty {
-                    CommandSyntax::Argument(name_ident, ty.clone())
-                } else {
-                    panic!("Unknown argument: {}", name);
-                }
+            if let Some(name) = segment.strip_prefix('<') {
+                let name = name.trim_end_matches('>');
+                arguments
+                    .iter()
+                    .find(|argument| argument.name == name)
+                    .or_else(|| arguments.iter().find(|argument| argument.ident == name))
+                    .map(|argument| CommandSyntax::Argument(argument.clone()))
+                    .ok_or_else(|| {
+                        Error::new(
+                            function.sig.ident.span(),
+                            format!("Unknown argument: {}", name),
+                        )
+                    })
             } else {
-                CommandSyntax::Literal(segment.to_string())
+                Ok(CommandSyntax::Literal(segment.to_string()))
             }
         })
-        .collect();
+        .collect()
 }
 
-fn check_context_arg(sig: &Signature, context_type: &Type) -> syn::Result<()> {
+/// Check that the first parameter is a reference to the context type and return whether it is mutable.
+fn check_context_arg(sig: &Signature, context_type: &Type) -> syn::Result<bool> {
     match sig.inputs.first() {
         Some(FnArg::Typed(PatType { ty, .. })) => match &**ty {
-            Type::Reference(TypeReference { elem, .. })
-                if elem.to_token_stream().to_string()
-                    == context_type.to_token_stream().to_string() =>
+            Type::Reference(TypeReference {
+                elem, mutability, ..
+            }) if elem.to_token_stream().to_string()
+                == context_type.to_token_stream().to_string() =>
             // Since syn::Type doesn't implement PartialEq, we have to convert to a string
             {
-                Ok(())
+                Ok(mutability.is_some())
             }
             _ => Err(Error::new(ty.span(), "Expected reference to context type")),
         },
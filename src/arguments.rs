@@ -0,0 +1,34 @@
+//! Additional `Argument` implementations beyond the basic types covered in the `parser` module.
+//! Implementations for foreign types are gated behind cargo features.
+
+#[cfg(feature = "chrono")]
+mod calendar;
+mod collections;
+mod dice;
+mod encoding;
+#[cfg(feature = "uuid")]
+mod ids;
+mod net;
+mod numeric;
+#[cfg(any(feature = "glob", feature = "regex"))]
+mod patterns;
+mod selector;
+mod spatial;
+mod text;
+mod time;
+#[cfg(feature = "url")]
+mod urls;
+
+#[cfg(feature = "bitflags")]
+pub use collections::BitFlags;
+pub use collections::{Flags, KvMap, List, WeightedList};
+pub use dice::DiceRoll;
+pub use encoding::{Base64, Hex};
+pub use net::{Cidr, MacAddr};
+pub use numeric::{Bounded, ByteSize, Interval};
+pub use selector::{Selectable, Selector, SelectorFilter, SelectorTarget};
+pub use spatial::{Coordinate, Coordinates, Direction, Region};
+pub use text::{Choice, Choices, Email, FromStrArg, GreedyString, Identifier, LanguageTag, Word};
+pub use time::{Month, RelativeTime, Weekday};
+#[cfg(feature = "url")]
+pub use urls::{RestrictedUrl, UrlSchemes, WebSchemes};
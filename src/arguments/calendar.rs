@@ -0,0 +1,105 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+use crate::{
+    arguments::{Month, Weekday},
+    parser::{Argument, CommandParser, ParseError, ParseErrorKind},
+};
+
+/// Parses an ISO 8601 calendar date such as `2024-05-17`.
+impl Argument for NaiveDate {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let date = parser.read_while(|c| !c.is_whitespace());
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+/// Parses an ISO 8601 time of day such as `14:30` or `14:30:15.250`.
+impl Argument for NaiveTime {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let time = parser.read_while(|c| !c.is_whitespace());
+        NaiveTime::parse_from_str(time, "%H:%M:%S%.f")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+            .map_err(|_| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+/// Parses an RFC 3339 timestamp such as `2024-05-17T14:30:00Z` or `2024-05-17T16:30:00+02:00`,
+/// converting it to UTC.
+impl Argument for DateTime<Utc> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let timestamp = parser.read_while(|c| !c.is_whitespace());
+        DateTime::parse_from_rfc3339(timestamp)
+            .map(|timestamp| timestamp.with_timezone(&Utc))
+            .map_err(|_| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+impl From<Weekday> for chrono::Weekday {
+    fn from(weekday: Weekday) -> Self {
+        match weekday {
+            Weekday::Monday => chrono::Weekday::Mon,
+            Weekday::Tuesday => chrono::Weekday::Tue,
+            Weekday::Wednesday => chrono::Weekday::Wed,
+            Weekday::Thursday => chrono::Weekday::Thu,
+            Weekday::Friday => chrono::Weekday::Fri,
+            Weekday::Saturday => chrono::Weekday::Sat,
+            Weekday::Sunday => chrono::Weekday::Sun,
+        }
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(weekday: chrono::Weekday) -> Self {
+        match weekday {
+            chrono::Weekday::Mon => Weekday::Monday,
+            chrono::Weekday::Tue => Weekday::Tuesday,
+            chrono::Weekday::Wed => Weekday::Wednesday,
+            chrono::Weekday::Thu => Weekday::Thursday,
+            chrono::Weekday::Fri => Weekday::Friday,
+            chrono::Weekday::Sat => Weekday::Saturday,
+            chrono::Weekday::Sun => Weekday::Sunday,
+        }
+    }
+}
+
+impl From<Month> for chrono::Month {
+    fn from(month: Month) -> Self {
+        match month {
+            Month::January => chrono::Month::January,
+            Month::February => chrono::Month::February,
+            Month::March => chrono::Month::March,
+            Month::April => chrono::Month::April,
+            Month::May => chrono::Month::May,
+            Month::June => chrono::Month::June,
+            Month::July => chrono::Month::July,
+            Month::August => chrono::Month::August,
+            Month::September => chrono::Month::September,
+            Month::October => chrono::Month::October,
+            Month::November => chrono::Month::November,
+            Month::December => chrono::Month::December,
+        }
+    }
+}
+
+impl From<chrono::Month> for Month {
+    fn from(month: chrono::Month) -> Self {
+        match month {
+            chrono::Month::January => Month::January,
+            chrono::Month::February => Month::February,
+            chrono::Month::March => Month::March,
+            chrono::Month::April => Month::April,
+            chrono::Month::May => Month::May,
+            chrono::Month::June => Month::June,
+            chrono::Month::July => Month::July,
+            chrono::Month::August => Month::August,
+            chrono::Month::September => Month::September,
+            chrono::Month::October => Month::October,
+            chrono::Month::November => Month::November,
+            chrono::Month::December => Month::December,
+        }
+    }
+}
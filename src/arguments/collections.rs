@@ -0,0 +1,247 @@
+use std::{collections::HashMap, ops::Deref};
+
+use crate::parser::{Argument, CommandParser, Number, NumberFormat, ParseError, ParseErrorKind};
+
+/// A list of weighted choices such as `zombie:3,skeleton:1`.
+/// Every weight has to be a positive integer, whose digits can't be grouped by commas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightedList(pub Vec<(String, u32)>);
+
+impl WeightedList {
+    /// The sum of all weights in the list.
+    pub fn total_weight(&self) -> u64 {
+        self.0.iter().map(|(_, weight)| u64::from(*weight)).sum()
+    }
+
+    /// Unwrap the underlying list of choices.
+    pub fn into_inner(self) -> Vec<(String, u32)> {
+        self.0
+    }
+}
+
+impl Argument for WeightedList {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let mut choices = vec![];
+        loop {
+            let name = parser.read_while(|c| c != ':' && c != ',' && !c.is_whitespace());
+            if name.is_empty() {
+                return Err(parser.error(ParseErrorKind::BadArgument));
+            }
+            parser.lit(":")?;
+            let weight_start = parser.branch();
+            let format = NumberFormat::from_config(parser.config()).without_separator(',');
+            let weight = u32::parse_with(parser, format)?;
+            if weight == 0 {
+                return Err(weight_start.error(ParseErrorKind::BadArgument));
+            }
+            choices.push((name.to_string(), weight));
+
+            if parser.lit(",").is_err() {
+                break;
+            }
+        }
+        Ok(WeightedList(choices))
+    }
+}
+
+/// Whitespace-separated `key=value` pairs such as `mode=fast label="first try"`.
+/// Values may be quoted to include whitespace. A pair without a value like `mode=` is rejected,
+/// while `mode=""` gives an empty value. If a key appears several times, the last value is kept.
+/// Parsing stops before the first token that isn't a pair, so further arguments may follow.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KvMap(pub HashMap<String, String>);
+
+impl KvMap {
+    /// Get the value of the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    /// Unwrap the underlying map.
+    pub fn into_inner(self) -> HashMap<String, String> {
+        self.0
+    }
+}
+
+/// Parse a single `key=value` pair.
+fn parse_pair<'a>(parser: &mut CommandParser<'a>) -> Result<(String, String), ParseError<'a>> {
+    let start = parser.branch();
+    let key = parser.read_while(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.');
+    if key.is_empty() {
+        return Err(start.error(ParseErrorKind::BadArgument));
+    }
+    parser.lit("=")?;
+    let value = if parser.remaining().starts_with('"') {
+        parser.argument::<String>()?
+    } else {
+        let value = parser.read_while(|c| !c.is_whitespace());
+        if value.is_empty() {
+            return Err(parser.error(ParseErrorKind::BadArgument));
+        }
+        value.to_string()
+    };
+    Ok((key.to_string(), value))
+}
+
+impl Argument for KvMap {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let (key, value) = parse_pair(parser)?;
+        let mut pairs = HashMap::from([(key, value)]);
+        loop {
+            let mut rest = parser.branch();
+            if rest.spacing().is_err() {
+                break;
+            }
+            match parse_pair(&mut rest) {
+                Ok((key, value)) => {
+                    pairs.insert(key, value);
+                    *parser = rest;
+                }
+                Err(_) => break,
+            }
+        }
+        Ok(KvMap(pairs))
+    }
+}
+
+/// A list of values separated by commas, such as `1,2,3` or `north, east`.
+/// Unlike `Vec<T>`, the list is a single argument, so further arguments may follow it.
+/// Unquoted values end at the next comma or whitespace.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct List<T>(pub Vec<T>);
+
+impl<T> List<T> {
+    /// Unwrap the underlying list of values.
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T> Deref for List<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T: Argument> Argument for List<T> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let mut values = vec![];
+        loop {
+            let value = if parser.remaining().starts_with('"') {
+                parser.argument::<T>()?
+            } else {
+                let len = parser
+                    .remaining()
+                    .find(|c: char| c == ',' || c.is_whitespace())
+                    .unwrap_or(parser.remaining().len());
+                let mut element = parser.truncated(len);
+                let value = element
+                    .argument::<T>()
+                    .and_then(|value| element.end().map(|()| value))
+                    .map_err(|error| parser.widen_error(error))?;
+                parser.advance(len);
+                value
+            };
+            values.push(value);
+
+            let mut rest = parser.branch();
+            rest.read_while(char::is_whitespace);
+            if rest.lit(",").is_err() {
+                break;
+            }
+            rest.read_while(char::is_whitespace);
+            *parser = rest;
+        }
+        Ok(List(values))
+    }
+
+    /// Suggests the values of `T`, following any values that have already been typed.
+    fn suggestions(parser: &CommandParser) -> Vec<String> {
+        let token = parser
+            .remaining()
+            .split(char::is_whitespace)
+            .next()
+            .unwrap_or_default();
+        let typed = token.rfind(',').map_or("", |i| &token[..=i]);
+        T::suggestions(parser)
+            .into_iter()
+            .map(|value| format!("{}{}", typed, value))
+            .collect()
+    }
+}
+
+/// A set of values separated by `|`, such as `errors|warnings`.
+/// Duplicate values are only included once, in the order they first appeared.
+/// Combine with `#[derive(Argument)]` on an enum to parse a set of its variants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flags<E>(pub Vec<E>);
+
+impl<E: PartialEq> Flags<E> {
+    /// Check whether the given value is part of the set.
+    pub fn contains(&self, value: &E) -> bool {
+        self.0.contains(value)
+    }
+
+    /// Unwrap the underlying list of values.
+    pub fn into_inner(self) -> Vec<E> {
+        self.0
+    }
+}
+
+impl<E: Argument + PartialEq> Argument for Flags<E> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let mut values = vec![];
+        loop {
+            let value = parser.argument::<E>()?;
+            if !values.contains(&value) {
+                values.push(value);
+            }
+            if parser.lit("|").is_err() {
+                break;
+            }
+        }
+        Ok(Flags(values))
+    }
+
+    /// Suggests the values of `E`, following any values that have already been typed.
+    fn suggestions(parser: &CommandParser) -> Vec<String> {
+        let token = parser
+            .remaining()
+            .split(char::is_whitespace)
+            .next()
+            .unwrap_or_default();
+        let typed = token.rfind('|').map_or("", |i| &token[..=i]);
+        E::suggestions(parser)
+            .into_iter()
+            .map(|value| format!("{}{}", typed, value))
+            .collect()
+    }
+}
+
+/// A `bitflags` type parsed from the names of its flags separated by `|`, e.g. `READ|write`.
+/// Names are matched ignoring case.
+#[cfg(feature = "bitflags")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitFlags<F>(pub F);
+
+#[cfg(feature = "bitflags")]
+impl<F: bitflags::Flags> Argument for BitFlags<F> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let mut flags = F::empty();
+        loop {
+            let start = parser.branch();
+            let name = parser.read_while(|c| c.is_alphanumeric() || c == '_');
+            let flag = F::FLAGS
+                .iter()
+                .find(|flag| flag.is_named() && flag.name().eq_ignore_ascii_case(name))
+                .ok_or_else(|| start.error(ParseErrorKind::BadArgument))?;
+            flags.insert(F::from_bits_retain(flag.value().bits()));
+            if parser.lit("|").is_err() {
+                break;
+            }
+        }
+        Ok(BitFlags(flags))
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// A roll of dice in the usual notation, e.g. `2d6+3`, `d20` or `4d6-1`.
+/// The number of dice defaults to one, and `d%` is short for `d100`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DiceRoll {
+    /// The number of dice to roll, from one up to `DiceRoll::MAX_COUNT`.
+    pub count: u32,
+    /// The number of sides of each die, which is at least one.
+    pub sides: u32,
+    /// The number added to the sum of the dice.
+    pub modifier: i32,
+}
+
+impl DiceRoll {
+    /// The largest number of dice accepted when parsing, which keeps `roll_with` from rolling for too long.
+    pub const MAX_COUNT: u32 = 1000;
+
+    /// The lowest possible result.
+    pub fn min(&self) -> i64 {
+        i64::from(self.count) + i64::from(self.modifier)
+    }
+
+    /// The highest possible result, saturating at `i64::MAX`.
+    pub fn max(&self) -> i64 {
+        i64::from(self.count)
+            .saturating_mul(i64::from(self.sides))
+            .saturating_add(i64::from(self.modifier))
+    }
+
+    /// The average result.
+    pub fn average(&self) -> f64 {
+        (self.min() as f64 + self.max() as f64) / 2.0
+    }
+
+    /// Roll the dice using `die`, which is called once for each die with the number of sides
+    /// and has to return a number from 1 up to that number, e.g. using a random number generator.
+    /// The result saturates at `i64::MAX`.
+    pub fn roll_with(&self, mut die: impl FnMut(u32) -> u32) -> i64 {
+        (0..self.count)
+            .map(|_| i64::from(die(self.sides)))
+            .fold(i64::from(self.modifier), i64::saturating_add)
+    }
+}
+
+impl Display for DiceRoll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.modifier {
+            0 => Ok(()),
+            modifier => write!(f, "{:+}", modifier),
+        }
+    }
+}
+
+impl Argument for DiceRoll {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let count = match parser.read_while(|c| c.is_ascii_digit()) {
+            "" => Some(1),
+            count => count.parse().ok(),
+        };
+        if parser.lit("d").is_err() && parser.lit("D").is_err() {
+            return Err(start.error(ParseErrorKind::BadArgument));
+        }
+        let sides = if parser.lit("%").is_ok() {
+            Some(100)
+        } else {
+            parser.read_while(|c| c.is_ascii_digit()).parse().ok()
+        };
+        let modifier = match parser.remaining().chars().next() {
+            Some(sign @ ('+' | '-')) => {
+                parser.advance(1);
+                let modifier = parser.read_while(|c| c.is_ascii_digit()).parse::<i32>();
+                modifier.ok().map(|modifier| match sign {
+                    '-' => -modifier,
+                    _ => modifier,
+                })
+            }
+            _ => Some(0),
+        };
+        match (count, sides, modifier) {
+            (Some(count @ 1..=Self::MAX_COUNT), Some(sides @ 1..), Some(modifier)) => {
+                Ok(DiceRoll {
+                    count,
+                    sides,
+                    modifier,
+                })
+            }
+            _ => Err(start.error(ParseErrorKind::BadArgument)),
+        }
+    }
+
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        ["d4", "d6", "d8", "d10", "d12", "d20", "d%"]
+            .map(String::from)
+            .to_vec()
+    }
+}
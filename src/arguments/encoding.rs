@@ -0,0 +1,95 @@
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// Binary data encoded as base64, decoded into `T`.
+/// Either the standard or the URL-safe alphabet is accepted, but not a mix of both. Padding is optional.
+/// Payloads decoding to more than `MAX` bytes are rejected.
+/// `T` can be any type constructible from a `Vec<u8>`, e.g. `[u8; 32]` for fixed-size signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Base64<T = Vec<u8>, const MAX: usize = 4096>(pub T);
+
+/// Binary data encoded as hexadecimal digits, with an optional `0x` prefix, decoded into `T`.
+/// Payloads decoding to more than `MAX` bytes are rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hex<T = Vec<u8>, const MAX: usize = 4096>(pub T);
+
+/// Decode base64 in a single alphabet, with padding only where the length requires it and no stray bits left over.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let data = text.trim_end_matches('=');
+    let padding = text.len() - data.len();
+    let padded = padding > 0;
+    if data.is_empty() || data.len() % 4 == 1 || padded && (data.len() + padding) % 4 != 0 {
+        return None;
+    }
+    let standard = data.contains(['+', '/']);
+    let url_safe = data.contains(['-', '_']);
+    if standard && url_safe {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in data.chars() {
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' | '-' => 62,
+            '/' | '_' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    // The bits left over after the last full byte must be zero, so every payload has exactly one encoding
+    (buffer == 0).then_some(bytes)
+}
+
+/// Decode pairs of hex digits, rejecting anything else such as signs and an empty payload after the `0x` prefix.
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.strip_prefix("0x").unwrap_or(text).as_bytes();
+    let pairs = text.chunks_exact(2);
+    if text.is_empty() || !pairs.remainder().is_empty() {
+        return None;
+    }
+    let digit = |c: u8| char::from(c).to_digit(16);
+    pairs
+        .map(|pair| Some((digit(pair[0])? << 4 | digit(pair[1])?) as u8))
+        .collect()
+}
+
+/// Read a whitespace-delimited token and decode it into `T`, enforcing the length limit.
+/// `max_len` is the longest text that can encode `max` bytes.
+fn parse_encoded<'a, T: TryFrom<Vec<u8>>>(
+    parser: &mut CommandParser<'a>,
+    max: usize,
+    max_len: usize,
+    decode: fn(&str) -> Option<Vec<u8>>,
+) -> Result<T, ParseError<'a>> {
+    let start = parser.branch();
+    let text = parser.read_while(|c| !c.is_whitespace());
+    // Don't decode tokens that can't possibly fit, so oversized input is rejected without allocating for it
+    if text.len() > max_len {
+        return Err(start.error(ParseErrorKind::BadArgument));
+    }
+    decode(text)
+        .filter(|bytes| !text.is_empty() && bytes.len() <= max)
+        .and_then(|bytes| T::try_from(bytes).ok())
+        .ok_or_else(|| start.error(ParseErrorKind::BadArgument))
+}
+
+impl<T: TryFrom<Vec<u8>>, const MAX: usize> Argument for Base64<T, MAX> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        parse_encoded(parser, MAX, MAX.div_ceil(3) * 4, decode_base64).map(Base64)
+    }
+}
+
+impl<T: TryFrom<Vec<u8>>, const MAX: usize> Argument for Hex<T, MAX> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        parse_encoded(parser, MAX, MAX * 2 + "0x".len(), decode_hex).map(Hex)
+    }
+}
@@ -0,0 +1,16 @@
+use uuid::Uuid;
+
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// Parses a UUID in either its hyphenated form, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`,
+/// or its simple form without hyphens, ignoring case.
+impl Argument for Uuid {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let uuid = parser.read_while(|c| c.is_ascii_hexdigit() || c == '-');
+        match uuid.len() {
+            32 | 36 => Uuid::try_parse(uuid).map_err(|_| start.error(ParseErrorKind::BadArgument)),
+            _ => Err(start.error(ParseErrorKind::BadArgument)),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// An IPv4 or IPv6 network in CIDR notation, like `10.0.0.0/8` or `2001:db8::/32`.
+/// A bare address without a prefix length is treated as a network containing only that address.
+/// Host bits set in the parsed address are cleared, so `10.1.2.3/8` is the same network as `10.0.0.0/8`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Create the network of the given prefix length containing `address`.
+    /// Returns `None` if the prefix length is larger than the address (32 bits for IPv4, 128 for IPv6).
+    pub fn new(address: IpAddr, prefix: u8) -> Option<Self> {
+        let network = match address {
+            IpAddr::V4(address) if prefix <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(address) & mask))
+            }
+            IpAddr::V6(address) if prefix <= 128 => {
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(address) & mask))
+            }
+            _ => return None,
+        };
+        Some(Self { network, prefix })
+    }
+
+    /// The first address of the network.
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    /// The number of leading bits shared by all addresses in the network.
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// Whether `address` lies within this network. Addresses of the other IP version are never contained.
+    pub fn contains(&self, address: &IpAddr) -> bool {
+        match (self.network, address) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                Self::new(*address, self.prefix).map(|other| other.network) == Some(self.network)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `other` is entirely contained in this network.
+    pub fn contains_network(&self, other: &Cidr) -> bool {
+        other.prefix >= self.prefix && self.contains(&other.network)
+    }
+}
+
+impl std::fmt::Display for Cidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+impl Argument for Cidr {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let text = parser.read_while(|c| c.is_ascii_hexdigit() || matches!(c, '.' | ':' | '/'));
+        let (address, prefix) = match text.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (text, None),
+        };
+        let address: IpAddr = address
+            .parse()
+            .map_err(|_| start.error(ParseErrorKind::BadArgument))?;
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse()
+                .map_err(|_| start.error(ParseErrorKind::BadArgument))?,
+            None if address.is_ipv4() => 32,
+            None => 128,
+        };
+        let max = if address.is_ipv4() { 32 } else { 128 };
+        Cidr::new(address, prefix)
+            .ok_or_else(|| start.error(ParseErrorKind::OutOfBounds { min: 0, max }))
+    }
+}
+
+/// A MAC address, given as six pairs of hexadecimal digits separated by colons or hyphens,
+/// like `00:1a:2b:3c:4d:5e` or `00-1A-2B-3C-4D-5E`. The same separator has to be used throughout.
+/// Displayed in lowercase, separated by colons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddr([u8; 6]);
+
+impl MacAddr {
+    /// Create a MAC address from its six bytes.
+    pub fn new(octets: [u8; 6]) -> Self {
+        Self(octets)
+    }
+
+    /// The six bytes of the address.
+    pub fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+}
+
+impl std::fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+impl Argument for MacAddr {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let text = parser.read_while(|c| c.is_ascii_hexdigit() || c == ':' || c == '-');
+        let separator = if text.contains(':') { ':' } else { '-' };
+        let mut octets = [0; 6];
+        let mut parts = text.split(separator);
+        for octet in &mut octets {
+            *octet = parts
+                .next()
+                .filter(|part| part.len() == 2)
+                .and_then(|part| u8::from_str_radix(part, 16).ok())
+                .ok_or_else(|| start.error(ParseErrorKind::BadArgument))?;
+        }
+        if parts.next().is_some() {
+            return Err(start.error(ParseErrorKind::BadArgument));
+        }
+        Ok(MacAddr(octets))
+    }
+}
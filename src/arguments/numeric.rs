@@ -0,0 +1,227 @@
+use std::ops::{Bound, RangeBounds};
+
+#[cfg(feature = "rust_decimal")]
+use rust_decimal::Decimal;
+
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+#[cfg(feature = "rust_decimal")]
+use crate::parser::{Number, NumberFormat};
+
+/// A range of values written like Rust's range expressions: `1..10`, `3..=8`, `..5`, `2..` or `..`.
+/// Parsing fails if the start is greater than the end.
+/// Implements `RangeBounds`, so it can be checked with `contains` or used to index slices via `bounds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    pub start: Bound<T>,
+    pub end: Bound<T>,
+}
+
+impl<T> Interval<T> {
+    /// The bounds of this interval as a tuple, which can be used to index slices when `T` is `usize`.
+    pub fn bounds(self) -> (Bound<T>, Bound<T>) {
+        (self.start, self.end)
+    }
+}
+
+impl<T> RangeBounds<T> for Interval<T> {
+    fn start_bound(&self) -> Bound<&T> {
+        self.start.as_ref()
+    }
+
+    fn end_bound(&self) -> Bound<&T> {
+        self.end.as_ref()
+    }
+}
+
+/// Parse the `len` bytes following the position of `parser` as one side of an interval, which is unbounded if empty.
+/// The bound is parsed with the parser's config, and errors point into the full command.
+fn parse_bound<'a, T: Argument>(
+    parser: &CommandParser<'a>,
+    len: usize,
+) -> Result<Option<T>, ParseError<'a>> {
+    if len == 0 {
+        return Ok(None);
+    }
+    let mut bound = parser.truncated(len);
+    bound
+        .argument()
+        .and_then(|value| bound.end().map(|()| Some(value)))
+        .map_err(|error| parser.widen_error(error))
+}
+
+impl<T: Argument + PartialOrd> Argument for Interval<T> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let token = parser.read_while(|c| !c.is_whitespace());
+        let bad = || start.error(ParseErrorKind::BadArgument);
+        let (low, high) = token.split_once("..").ok_or_else(bad)?;
+        let (high, inclusive) = match high.strip_prefix('=') {
+            Some(high) => (high, true),
+            None => (high, false),
+        };
+        let mut high_start = start.branch();
+        high_start.advance(token.len() - high.len());
+        let low = parse_bound::<T>(&start, low.len())?;
+        let high = parse_bound::<T>(&high_start, high.len())?;
+        if let (Some(low), Some(high)) = (&low, &high) {
+            if low > high {
+                return Err(bad());
+            }
+        }
+        let end = match high {
+            Some(high) if inclusive => Bound::Included(high),
+            Some(high) => Bound::Excluded(high),
+            // `..=` requires an end
+            None if inclusive => return Err(bad()),
+            None => Bound::Unbounded,
+        };
+        Ok(Interval {
+            start: low.map_or(Bound::Unbounded, Bound::Included),
+            end,
+        })
+    }
+}
+
+/// A number restricted to the range `MIN..=MAX`.
+/// Values outside of the range are rejected with `ParseErrorKind::OutOfBounds`, which includes the allowed bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bounded<T, const MIN: i128, const MAX: i128>(pub T);
+
+impl<T, const MIN: i128, const MAX: i128> Argument for Bounded<T, MIN, MAX>
+where
+    T: Argument + Copy,
+    i128: TryFrom<T>,
+{
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let value = parser.argument::<T>()?;
+        match i128::try_from(value) {
+            Ok(number) if (MIN..=MAX).contains(&number) => Ok(Bounded(value)),
+            _ => Err(start.error(ParseErrorKind::OutOfBounds { min: MIN, max: MAX })),
+        }
+    }
+
+    fn suggestions(parser: &CommandParser) -> Vec<String> {
+        T::suggestions(parser)
+    }
+}
+
+/// A number of bytes such as `512`, `10KiB` or `1.5GB`.
+/// Units are matched ignoring case: `B`, `KB`, `MB`, `GB`, `TB` and `PB` are powers of 1000,
+/// while `KiB`, `MiB`, `GiB`, `TiB` and `PiB` are powers of 1024. Fractions of a byte are rounded down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    /// The number of bytes.
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+}
+
+const BYTE_UNITS: [(&str, u128); 11] = [
+    ("b", 1),
+    ("kb", 1000),
+    ("mb", 1000u128.pow(2)),
+    ("gb", 1000u128.pow(3)),
+    ("tb", 1000u128.pow(4)),
+    ("pb", 1000u128.pow(5)),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+    ("pib", 1 << 50),
+];
+
+impl Argument for ByteSize {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let whole = parser.read_while(|c| c.is_ascii_digit());
+        let fraction = if parser.lit(".").is_ok() {
+            parser.read_while(|c| c.is_ascii_digit())
+        } else {
+            ""
+        };
+        let unit = parser.read_while(char::is_alphabetic).to_lowercase();
+        let multiplier = match unit.as_str() {
+            "" => Some(1),
+            unit => BYTE_UNITS
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|(_, multiplier)| *multiplier),
+        };
+        // Digits beyond the precision of the largest unit cannot affect the result
+        let fraction = &fraction[..fraction.len().min(18)];
+        let bytes = match (whole.parse::<u128>(), multiplier) {
+            (Ok(whole), Some(multiplier)) => {
+                let fraction = match fraction {
+                    "" => 0,
+                    digits => {
+                        let scale = 10u128.pow(digits.len() as u32);
+                        digits.parse::<u128>().unwrap_or(0) * multiplier / scale
+                    }
+                };
+                whole
+                    .checked_mul(multiplier)
+                    .and_then(|bytes| bytes.checked_add(fraction))
+            }
+            _ => None,
+        };
+        bytes
+            .and_then(|bytes| u64::try_from(bytes).ok())
+            .map(ByteSize)
+            .ok_or_else(|| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+/// Parses an exact decimal number such as `12.50` or `-0.001`, keeping the number of decimal places given.
+/// Unlike floats, exponents and special values like `inf` are not accepted. Only base 10 is supported.
+/// Numbers with more decimal places than a `Decimal` can hold are rejected instead of rounded,
+/// as are numbers without digits on both sides of the decimal point, like `1.` or `.5`.
+#[cfg(feature = "rust_decimal")]
+impl Number for Decimal {
+    fn parse_with<'a>(
+        parser: &mut CommandParser<'a>,
+        format: NumberFormat,
+    ) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        if format.radix != 10 {
+            return Err(start.error(ParseErrorKind::BadArgument));
+        }
+        let negative = parser.remaining().starts_with('-');
+        if negative {
+            parser.advance(1);
+        }
+        let mut decimals = false;
+        let digits = crate::parser::read_digits(
+            parser,
+            |c| {
+                if c == '.' {
+                    if decimals {
+                        false
+                    } else {
+                        decimals = true;
+                        true
+                    }
+                } else {
+                    c.is_ascii_digit()
+                }
+            },
+            format.separator,
+        )
+        .ok_or_else(|| start.error(ParseErrorKind::BadArgument))?;
+        if digits.starts_with('.') || digits.ends_with('.') {
+            return Err(start.error(ParseErrorKind::BadArgument));
+        }
+        let value = Decimal::from_str_exact(&digits)
+            .map_err(|_| start.error(ParseErrorKind::BadArgument))?;
+        Ok(if negative { -value } else { value })
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl Argument for Decimal {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        Self::parse_with(parser, NumberFormat::from_config(parser.config()))
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "glob")]
+use glob::Pattern;
+#[cfg(feature = "regex")]
+use regex::{Regex, RegexBuilder};
+
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// Parses and compiles a glob pattern such as `*.log` or `logs/**/2024-??-*`.
+/// Patterns containing whitespace have to be quoted. Invalid patterns are rejected while parsing.
+#[cfg(feature = "glob")]
+impl Argument for Pattern {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let pattern = if parser.remaining().starts_with('"') {
+            parser.argument::<String>()?
+        } else {
+            parser.read_while(|c| !c.is_whitespace()).to_string()
+        };
+        if pattern.is_empty() {
+            return Err(start.error(ParseErrorKind::UnexpectedEof));
+        }
+        Pattern::new(&pattern).map_err(|_| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+/// Parses and compiles a regular expression written either as a quoted string, e.g. `"error|warn"`,
+/// or delimited by slashes and followed by flags, e.g. `/^error: .*/i`.
+/// Backslashes are kept as written, e.g. `"\d+"`, except that `\"` stands for a quote within quotes
+/// and `\/` for a slash within slashes. The flags are `i` for case-insensitive matching,
+/// `m` for multi-line mode, `s` for `.` matching line breaks, `x` for ignoring whitespace and `U` for swapping greediness.
+/// Invalid patterns fail with `ParseErrorKind::BadArgument` at the start of the pattern.
+#[cfg(feature = "regex")]
+impl Argument for Regex {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        if parser.lit("\"").is_ok() {
+            let pattern = read_pattern(parser, '"');
+            parser
+                .lit("\"")
+                .map_err(|_| start.error(ParseErrorKind::UnterminatedString))?;
+            return Regex::new(&pattern).map_err(|_| start.error(ParseErrorKind::BadArgument));
+        }
+
+        parser.lit("/")?;
+        let pattern = read_pattern(parser, '/');
+        parser
+            .lit("/")
+            .map_err(|_| start.error(ParseErrorKind::BadArgument))?;
+
+        let mut builder = RegexBuilder::new(&pattern);
+        let flags_start = parser.branch();
+        for flag in parser.read_while(|c| !c.is_whitespace()).chars() {
+            match flag {
+                'i' => builder.case_insensitive(true),
+                'm' => builder.multi_line(true),
+                's' => builder.dot_matches_new_line(true),
+                'x' => builder.ignore_whitespace(true),
+                'U' => builder.swap_greed(true),
+                _ => return Err(flags_start.error(ParseErrorKind::BadArgument)),
+            };
+        }
+        builder
+            .build()
+            .map_err(|_| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+/// Read a regular expression up to the unescaped `delimiter`, only unescaping the delimiter itself
+/// so that escapes like `\d` reach the regex engine unchanged.
+#[cfg(feature = "regex")]
+fn read_pattern(parser: &mut CommandParser, delimiter: char) -> String {
+    let mut pattern = String::new();
+    let mut escaped = false;
+    parser.read_while(|c| {
+        if escaped {
+            if c != delimiter {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            return false;
+        } else {
+            pattern.push(c);
+        }
+        true
+    });
+    pattern
+}
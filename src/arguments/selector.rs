@@ -0,0 +1,167 @@
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// Which entities a `Selector` starts out with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectorTarget {
+    /// `@p`: the nearest player.
+    NearestPlayer,
+    /// `@r`: a random player.
+    RandomPlayer,
+    /// `@a`: all players.
+    AllPlayers,
+    /// `@e`: all entities.
+    AllEntities,
+    /// `@s`: the entity running the command.
+    Executor,
+}
+
+impl SelectorTarget {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'p' => Some(SelectorTarget::NearestPlayer),
+            'r' => Some(SelectorTarget::RandomPlayer),
+            'a' => Some(SelectorTarget::AllPlayers),
+            'e' => Some(SelectorTarget::AllEntities),
+            's' => Some(SelectorTarget::Executor),
+            _ => None,
+        }
+    }
+}
+
+/// A single `key=value` condition of a `Selector`, e.g. `type=zombie` or `tag=!hostile`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SelectorFilter {
+    pub key: String,
+    pub value: String,
+    /// Whether the value was prefixed with `!`, selecting entities which don't match it.
+    pub negated: bool,
+}
+
+/// Entities which can be selected using a `Selector`.
+pub trait Selectable {
+    fn is_player(&self) -> bool;
+
+    /// Whether this is the entity running the command, which is selected by `@s`.
+    fn is_executor(&self) -> bool;
+
+    /// Whether this entity matches the filter `key=value`, e.g. `type=zombie`. Negation is handled by the selector.
+    fn matches(&self, key: &str, value: &str) -> bool;
+}
+
+/// A Minecraft-style entity selector such as `@a`, `@p` or `@e[type=zombie,limit=3]`.
+/// Values in filters may be quoted to include `,` or `]`, and prefixed with `!` to negate them.
+/// The `limit` filter has to be a non-negative number and can't be negated.
+/// The selector is applied against the entities of the context using `apply`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Selector {
+    pub target: SelectorTarget,
+    pub filters: Vec<SelectorFilter>,
+}
+
+impl Selector {
+    /// The value of the `limit` filter, which defaults to one for `@p`, `@r` and `@s`.
+    pub fn limit(&self) -> Option<usize> {
+        self.filters
+            .iter()
+            .find(|filter| filter.key == "limit" && !filter.negated)
+            .and_then(|filter| filter.value.parse().ok())
+            .or(match self.target {
+                SelectorTarget::NearestPlayer
+                | SelectorTarget::RandomPlayer
+                | SelectorTarget::Executor => Some(1),
+                SelectorTarget::AllPlayers | SelectorTarget::AllEntities => None,
+            })
+    }
+
+    /// Select the matching entities in the given order, up to the limit.
+    /// The entities should be sorted by distance for `@p` and shuffled for `@r`.
+    /// All filters except `limit` are checked using `Selectable::matches`.
+    pub fn apply<'e, E: Selectable>(
+        &self,
+        entities: impl IntoIterator<Item = &'e E>,
+    ) -> Vec<&'e E> {
+        let selected = entities
+            .into_iter()
+            .filter(|entity| match self.target {
+                SelectorTarget::AllEntities => true,
+                SelectorTarget::Executor => entity.is_executor(),
+                _ => entity.is_player(),
+            })
+            .filter(|entity| {
+                self.filters
+                    .iter()
+                    .filter(|filter| filter.key != "limit")
+                    .all(|filter| entity.matches(&filter.key, &filter.value) != filter.negated)
+            });
+        match self.limit() {
+            Some(limit) => selected.take(limit).collect(),
+            None => selected.collect(),
+        }
+    }
+}
+
+/// Parse the value of a filter, which is either a quoted string or ends at `,` or `]`.
+fn parse_filter_value<'a>(parser: &mut CommandParser<'a>) -> Result<String, ParseError<'a>> {
+    if parser.remaining().starts_with('"') {
+        parser.argument::<String>()
+    } else {
+        let value = parser.read_while(|c| c != ',' && c != ']' && !c.is_whitespace());
+        Ok(value.to_string())
+    }
+}
+
+impl Argument for Selector {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        parser.lit("@")?;
+        let target = parser
+            .remaining()
+            .chars()
+            .next()
+            .and_then(SelectorTarget::from_char)
+            .ok_or_else(|| start.error(ParseErrorKind::BadArgument))?;
+        parser.advance(1);
+
+        let mut filters = vec![];
+        if parser.lit("[").is_ok() {
+            parser.read_while(char::is_whitespace);
+            while parser.lit("]").is_err() {
+                if !filters.is_empty() {
+                    parser.lit(",")?;
+                    parser.read_while(char::is_whitespace);
+                }
+                let key_start = parser.branch();
+                let key = parser.read_while(|c| c.is_alphanumeric() || c == '_');
+                if key.is_empty() {
+                    return Err(key_start.error(ParseErrorKind::BadArgument));
+                }
+                parser.read_while(char::is_whitespace);
+                parser.lit("=")?;
+                parser.read_while(char::is_whitespace);
+                let negation = parser.branch();
+                let negated = parser.lit("!").is_ok();
+                let value_start = parser.branch();
+                let value = parse_filter_value(parser)?;
+                if key == "limit" {
+                    if negated {
+                        return Err(negation.error(ParseErrorKind::BadArgument));
+                    }
+                    if value.parse::<usize>().is_err() {
+                        return Err(value_start.error(ParseErrorKind::BadArgument));
+                    }
+                }
+                parser.read_while(char::is_whitespace);
+                filters.push(SelectorFilter {
+                    key: key.to_string(),
+                    value,
+                    negated,
+                });
+            }
+        }
+        Ok(Selector { target, filters })
+    }
+
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        ["@a", "@e", "@p", "@r", "@s"].map(String::from).to_vec()
+    }
+}
@@ -0,0 +1,191 @@
+use crate::parser::{Argument, CommandParser, Number, NumberFormat, ParseError, ParseErrorKind};
+
+/// One of the six axis-aligned directions.
+/// Parses the full names `north`, `south`, `east`, `west`, `up` and `down` as well as their
+/// single-letter abbreviations, ignoring case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    Up,
+    Down,
+}
+
+impl Direction {
+    /// The unit vector pointing in this direction as `[x, y, z]`.
+    /// Follows the usual block game convention where north is negative z and east is positive x.
+    pub fn unit_vector(self) -> [i32; 3] {
+        match self {
+            Direction::North => [0, 0, -1],
+            Direction::South => [0, 0, 1],
+            Direction::East => [1, 0, 0],
+            Direction::West => [-1, 0, 0],
+            Direction::Up => [0, 1, 0],
+            Direction::Down => [0, -1, 0],
+        }
+    }
+}
+
+impl Argument for Direction {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let name = parser.read_while(char::is_alphabetic).to_lowercase();
+        match name.as_str() {
+            "north" | "n" => Ok(Direction::North),
+            "south" | "s" => Ok(Direction::South),
+            "east" | "e" => Ok(Direction::East),
+            "west" | "w" => Ok(Direction::West),
+            "up" | "u" => Ok(Direction::Up),
+            "down" | "d" => Ok(Direction::Down),
+            _ => Err(start.error(ParseErrorKind::BadArgument)),
+        }
+    }
+
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        ["north", "south", "east", "west", "up", "down"]
+            .map(String::from)
+            .to_vec()
+    }
+}
+
+/// An axis-aligned bounding box spanning `N` dimensions, defaulting to three.
+/// Accepts either `from 0 0 0 to 15 255 15` or the compact form `0,0,0 15,255,15`,
+/// whose components can't have their digits grouped by commas.
+/// Parsing fails if any component of the first corner is greater than that of the second one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Region<const N: usize = 3> {
+    pub min: [i32; N],
+    pub max: [i32; N],
+}
+
+impl<const N: usize> Region<N> {
+    /// Check whether the given point lies within this region, bounds included.
+    pub fn contains(&self, point: [i32; N]) -> bool {
+        (0..N).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+}
+
+/// Parse `N` integer components, separated by whitespace if `compact` is false and by commas otherwise,
+/// in which case commas aren't accepted as digit separators.
+fn parse_point<'a, const N: usize>(
+    parser: &mut CommandParser<'a>,
+    compact: bool,
+) -> Result<[i32; N], ParseError<'a>> {
+    let mut format = NumberFormat::from_config(parser.config());
+    if compact {
+        format = format.without_separator(',');
+    }
+    let mut point = [0; N];
+    for (i, component) in point.iter_mut().enumerate() {
+        if i > 0 {
+            if compact {
+                parser.lit(",")?;
+            } else {
+                parser.spacing()?;
+            }
+        }
+        *component = i32::parse_with(parser, format)?;
+    }
+    Ok(point)
+}
+
+impl<const N: usize> Argument for Region<N> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let (min, max) = if parser.lit("from").is_ok() {
+            parser.spacing()?;
+            let min = parse_point(parser, false)?;
+            parser.spacing()?;
+            parser.lit("to")?;
+            parser.spacing()?;
+            (min, parse_point(parser, false)?)
+        } else {
+            let min = parse_point(parser, true)?;
+            parser.spacing()?;
+            (min, parse_point(parser, true)?)
+        };
+
+        if (0..N).all(|i| min[i] <= max[i]) {
+            Ok(Region { min, max })
+        } else {
+            Err(start.error(ParseErrorKind::BadArgument))
+        }
+    }
+}
+
+/// A single component of `Coordinates`, either absolute or relative to a base position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinate {
+    /// A plain number such as `12.5`.
+    Absolute(f64),
+    /// An offset from the base position such as `~`, `~1.5` or `~-2`.
+    Relative(f64),
+}
+
+impl Coordinate {
+    /// Resolve this component against the corresponding component of the base position.
+    pub fn resolve(self, base: f64) -> f64 {
+        match self {
+            Coordinate::Absolute(value) => value,
+            Coordinate::Relative(offset) => base + offset,
+        }
+    }
+}
+
+impl Argument for Coordinate {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        if parser.lit("~").is_err() {
+            return parser.argument().map(Coordinate::Absolute);
+        }
+        if parser.remaining().starts_with(char::is_whitespace) || parser.remaining().is_empty() {
+            Ok(Coordinate::Relative(0.0))
+        } else {
+            parser.argument().map(Coordinate::Relative)
+        }
+    }
+}
+
+/// A position given as `x y z`, where each component may be relative to a base position using
+/// Minecraft-style notation, e.g. `~ ~1.5 10`.
+/// The base position, such as the position of the player running the command, is supplied when resolving it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+impl Coordinates {
+    /// Resolve the coordinates against the base position `[x, y, z]`.
+    pub fn resolve(&self, base: [f64; 3]) -> [f64; 3] {
+        [
+            self.x.resolve(base[0]),
+            self.y.resolve(base[1]),
+            self.z.resolve(base[2]),
+        ]
+    }
+
+    /// Whether any component is relative, in which case the result of `resolve` depends on the base position.
+    pub fn is_relative(&self) -> bool {
+        [self.x, self.y, self.z]
+            .iter()
+            .any(|component| matches!(component, Coordinate::Relative(_)))
+    }
+}
+
+impl Argument for Coordinates {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let x = parser.argument()?;
+        parser.spacing()?;
+        let y = parser.argument()?;
+        parser.spacing()?;
+        let z = parser.argument()?;
+        Ok(Coordinates { x, y, z })
+    }
+
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        vec!["~ ~ ~".to_string()]
+    }
+}
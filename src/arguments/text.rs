@@ -0,0 +1,450 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    marker::PhantomData,
+    ops::Deref,
+    str::FromStr,
+};
+
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// A BCP-47 language tag such as `de`, `de-AT` or `zh-Hant-TW`.
+/// Only the overall structure of the tag is validated, not whether its subtags are registered.
+/// The tag is normalized to the conventional casing, e.g. `DE-at` becomes `de-AT`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LanguageTag(String);
+
+impl LanguageTag {
+    /// Validate and normalize a language tag.
+    pub fn new(tag: &str) -> Option<Self> {
+        let mut subtags = tag.split(['-', '_']);
+        let language = subtags.next()?;
+        if !matches!(language.len(), 2..=3 | 5..=8)
+            || !language.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return None;
+        }
+
+        let mut normalized = language.to_ascii_lowercase();
+        let mut extension = false;
+        let mut expects_subtag = false;
+        for subtag in subtags {
+            if subtag.is_empty()
+                || subtag.len() > 8
+                || !subtag.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return None;
+            }
+            normalized.push('-');
+            if subtag.len() == 1 {
+                // Singletons introduce extensions or private use subtags
+                extension = true;
+                expects_subtag = true;
+                normalized.push_str(&subtag.to_ascii_lowercase());
+                continue;
+            }
+            expects_subtag = false;
+            if extension {
+                normalized.push_str(&subtag.to_ascii_lowercase());
+            } else if subtag.len() == 4 && subtag.chars().all(|c| c.is_ascii_alphabetic()) {
+                // Script, e.g. `Hant`
+                normalized.push_str(&subtag[..1].to_ascii_uppercase());
+                normalized.push_str(&subtag[1..].to_ascii_lowercase());
+            } else if subtag.len() == 2 {
+                // Region, e.g. `AT`
+                normalized.push_str(&subtag.to_ascii_uppercase());
+            } else {
+                normalized.push_str(&subtag.to_ascii_lowercase());
+            }
+        }
+
+        if expects_subtag {
+            None
+        } else {
+            Some(LanguageTag(normalized))
+        }
+    }
+
+    /// The primary language subtag, e.g. `de` for `de-AT`.
+    pub fn language(&self) -> &str {
+        self.0.split('-').next().unwrap_or(&self.0)
+    }
+
+    /// The normalized tag.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for LanguageTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Argument for LanguageTag {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let tag = parser.read_while(|c| !c.is_whitespace());
+        LanguageTag::new(tag).ok_or_else(|| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+#[cfg(feature = "unic-langid")]
+impl Argument for unic_langid::LanguageIdentifier {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let tag = parser.read_while(|c| !c.is_whitespace());
+        tag.parse()
+            .map_err(|_| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+/// An email address such as `alex@example.com`.
+/// The local part may contain ASCII letters, digits and the special characters allowed by RFC 5322,
+/// separated by single dots. The domain has to consist of at least two labels of letters, digits and hyphens.
+/// Quoted local parts and address literals are not supported. The domain is normalized to lowercase.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Email {
+    address: String,
+    at: usize,
+}
+
+impl Email {
+    /// Validate an email address.
+    pub fn new(address: &str) -> Option<Self> {
+        Self::validate(address).ok()
+    }
+
+    /// Validate an email address, returning the byte offset of the first invalid character on failure.
+    fn validate(address: &str) -> Result<Self, usize> {
+        let Some(at) = address.find('@') else {
+            return Err(address.len());
+        };
+        let (local, domain) = (&address[..at], &address[at + 1..]);
+        if local.is_empty() || local.starts_with('.') {
+            return Err(0);
+        }
+        // Point at the first character past the limit, which isn't at byte 64 if the local part isn't ASCII
+        if let Some((offset, _)) = local.char_indices().nth(64) {
+            return Err(offset);
+        }
+        let mut previous = '.';
+        for (offset, c) in local.char_indices() {
+            let valid = c.is_ascii_alphanumeric()
+                || "!#$%&'*+/=?^_`{|}~-".contains(c)
+                || (c == '.' && previous != '.');
+            if !valid {
+                return Err(offset);
+            }
+            previous = c;
+        }
+        if previous == '.' {
+            return Err(at - 1);
+        }
+
+        let mut label_start = at + 1;
+        for label in domain.split('.') {
+            let invalid = label
+                .char_indices()
+                .find(|&(_, c)| !c.is_ascii_alphanumeric() && c != '-')
+                .map(|(offset, _)| offset);
+            if let Some(offset) = invalid {
+                return Err(label_start + offset);
+            }
+            if label.is_empty() || label.starts_with('-') {
+                return Err(label_start);
+            }
+            if label.len() > 63 || label.ends_with('-') {
+                return Err(label_start + label.len() - 1);
+            }
+            label_start += label.len() + 1;
+        }
+        if !domain.contains('.') || address.len() > 254 {
+            return Err(address.len());
+        }
+
+        Ok(Email {
+            address: format!("{}@{}", local, domain.to_ascii_lowercase()),
+            at,
+        })
+    }
+
+    /// The part before the `@`.
+    pub fn local_part(&self) -> &str {
+        &self.address[..self.at]
+    }
+
+    /// The part after the `@`, in lowercase.
+    pub fn domain(&self) -> &str {
+        &self.address[self.at + 1..]
+    }
+
+    /// The normalized address.
+    pub fn as_str(&self) -> &str {
+        &self.address
+    }
+}
+
+impl Display for Email {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.address)
+    }
+}
+
+/// Fails with the position of the first invalid character.
+impl Argument for Email {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let mut start = parser.branch();
+        let address = parser.read_while(|c| !c.is_whitespace());
+        Email::validate(address).map_err(|offset| {
+            start.advance(offset);
+            start.error(ParseErrorKind::BadArgument)
+        })
+    }
+}
+
+/// A namespaced resource identifier such as `minecraft:stone` or `textures/ui/button.png`.
+/// The namespace may only contain lowercase ASCII letters, digits, `_`, `-` and `.`, the path additionally `/`.
+/// When parsed, identifiers without a namespace use `ParserConfig::default_namespace` and are rejected if it isn't set.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Identifier {
+    namespace: String,
+    path: String,
+}
+
+impl Identifier {
+    /// Validate the namespace and path of an identifier.
+    pub fn new(namespace: &str, path: &str) -> Option<Self> {
+        let namespace_valid =
+            !namespace.is_empty() && namespace.chars().all(Self::is_namespace_char);
+        let path_valid =
+            !path.is_empty() && path.chars().all(|c| c == '/' || Self::is_namespace_char(c));
+        (namespace_valid && path_valid).then(|| Identifier {
+            namespace: namespace.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    /// Parse an identifier in the form `namespace:path`, falling back to `default_namespace` if it is omitted.
+    pub fn with_default(identifier: &str, default_namespace: &str) -> Option<Self> {
+        match identifier.split_once(':') {
+            Some((namespace, path)) => Self::new(namespace, path),
+            None => Self::new(default_namespace, identifier),
+        }
+    }
+
+    fn is_namespace_char(c: char) -> bool {
+        c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.')
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Display for Identifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
+
+impl Argument for Identifier {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let identifier = parser.read_while(|c| !c.is_whitespace());
+        if identifier.is_empty() {
+            return Err(start.error(ParseErrorKind::UnexpectedEof));
+        }
+        let identifier = match &parser.config().default_namespace {
+            Some(namespace) => Identifier::with_default(identifier, namespace),
+            None => identifier
+                .split_once(':')
+                .and_then(|(namespace, path)| Identifier::new(namespace, path)),
+        };
+        identifier.ok_or_else(|| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+/// The words accepted by a `Choice`.
+pub trait Choices {
+    const CHOICES: &'static [&'static str];
+}
+
+/// One of a fixed set of words given by `C`, e.g. `easy`, `normal` or `hard`, matched ignoring case.
+/// A lightweight alternative to deriving `Argument` for an enum, e.g. for alternatives within a single variant:
+/// only a unit struct implementing `Choices` has to be declared, and the chosen word is exposed by its index.
+pub struct Choice<C: Choices> {
+    index: usize,
+    choices: PhantomData<C>,
+}
+
+impl<C: Choices> Choice<C> {
+    /// The position of the chosen word in `C::CHOICES`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The chosen word as it is written in `C::CHOICES`.
+    pub fn as_str(&self) -> &'static str {
+        C::CHOICES[self.index]
+    }
+}
+
+// Implemented manually, as deriving would require `C` to implement the traits as well
+impl<C: Choices> Clone for Choice<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: Choices> Copy for Choice<C> {}
+
+impl<C: Choices> PartialEq for Choice<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<C: Choices> Eq for Choice<C> {}
+
+impl<C: Choices> Debug for Choice<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<C: Choices> Display for Choice<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<C: Choices> Argument for Choice<C> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let word = parser.read_while(|c| !c.is_whitespace());
+        if word.is_empty() {
+            return Err(start.error(ParseErrorKind::UnexpectedEof));
+        }
+        C::CHOICES
+            .iter()
+            .position(|choice| choice.eq_ignore_ascii_case(word))
+            .map(|index| Choice {
+                index,
+                choices: PhantomData,
+            })
+            .ok_or_else(|| start.error(ParseErrorKind::BadArgument))
+    }
+
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        C::CHOICES.iter().map(|choice| choice.to_string()).collect()
+    }
+}
+
+/// A single unquoted token, ending at the next whitespace, e.g. a player name or an identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Word(pub String);
+
+impl Word {
+    /// Unwrap the underlying string.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for Word {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Word {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Argument for Word {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let word = parser.read_while(|c| !c.is_whitespace());
+        if word.is_empty() {
+            Err(parser.error(ParseErrorKind::UnexpectedEof))
+        } else {
+            Ok(Word(word.to_string()))
+        }
+    }
+}
+
+/// Any type implementing `FromStr`, parsed from a single token ending at the next whitespace,
+/// e.g. `FromStrArg<std::net::SocketAddr>` or `FromStrArg<std::path::PathBuf>`.
+/// Conversion errors are reported as `ParseErrorKind::Custom` with their message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct FromStrArg<T>(pub T);
+
+impl<T> FromStrArg<T> {
+    /// Unwrap the parsed value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for FromStrArg<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Argument for FromStrArg<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        parser.parse_token().map(FromStrArg)
+    }
+}
+
+/// The rest of the command as-is, so messages like `say hello world` don't have to be quoted.
+/// `define_command!` only accepts it as the last argument of a variant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreedyString(pub String);
+
+impl GreedyString {
+    /// Unwrap the underlying string.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for GreedyString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for GreedyString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Argument for GreedyString {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let rest = parser.read_while(|_| true);
+        if rest.is_empty() {
+            Err(parser.error(ParseErrorKind::UnexpectedEof))
+        } else {
+            Ok(GreedyString(rest.to_string()))
+        }
+    }
+}
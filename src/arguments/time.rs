@@ -0,0 +1,312 @@
+use std::time::{Duration, SystemTime};
+
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// Parse a duration such as `15m`, `2h30m` or `1d`.
+/// Supported units are `ms`, `s`, `m`, `h`, `d` and `w`.
+fn parse_duration(text: &str) -> Option<Duration> {
+    if text.is_empty() {
+        return None;
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return None;
+        }
+        let amount = rest[..digits].parse::<u64>().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest.len()
+            - rest
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+                .len();
+        let duration = match &rest[..unit_len] {
+            "ms" => Duration::from_millis(amount),
+            "s" => Duration::from_secs(amount),
+            "m" => Duration::from_secs(amount.checked_mul(60)?),
+            "h" => Duration::from_secs(amount.checked_mul(60 * 60)?),
+            "d" => Duration::from_secs(amount.checked_mul(60 * 60 * 24)?),
+            "w" => Duration::from_secs(amount.checked_mul(60 * 60 * 24 * 7)?),
+            _ => return None,
+        };
+        total = total.checked_add(duration)?;
+        rest = &rest[unit_len..];
+    }
+    Some(total)
+}
+
+/// A point in time relative to when a command is run.
+/// Parses `now`, offsets like `in 5m`, `+2h`, `now+1d`, `5m` or `tomorrow`, past offsets like `-30m`,
+/// `now-1h`, `5m ago` or `yesterday`, and absolute Unix timestamps in seconds like `@1700000000`.
+/// Durations combine amounts with the units `ms`, `s`, `m`, `h`, `d` and `w`, e.g. `2h30m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelativeTime {
+    /// A time after the current one.
+    Later(Duration),
+    /// A time before the current one.
+    Earlier(Duration),
+    /// An absolute instant.
+    At(SystemTime),
+}
+
+impl RelativeTime {
+    /// Resolve this time against the given instant, returning `None` if the result can't be represented.
+    pub fn resolve(self, now: SystemTime) -> Option<SystemTime> {
+        match self {
+            RelativeTime::Later(duration) => now.checked_add(duration),
+            RelativeTime::Earlier(duration) => now.checked_sub(duration),
+            RelativeTime::At(instant) => Some(instant),
+        }
+    }
+
+    /// Resolve this time against the current time.
+    pub fn instant(self) -> Option<SystemTime> {
+        self.resolve(SystemTime::now())
+    }
+}
+
+/// Parse a single-word expression like `now`, `now+2h`, `-30m` or `tomorrow`.
+fn parse_relative(expression: &str) -> Option<RelativeTime> {
+    const DAY: Duration = Duration::from_secs(60 * 60 * 24);
+    match expression {
+        "now" => return Some(RelativeTime::Later(Duration::ZERO)),
+        "tomorrow" => return Some(RelativeTime::Later(DAY)),
+        "yesterday" => return Some(RelativeTime::Earlier(DAY)),
+        _ => {}
+    }
+    if let Some(seconds) = expression.strip_prefix('@') {
+        let seconds = seconds.parse().ok()?;
+        return SystemTime::UNIX_EPOCH
+            .checked_add(Duration::from_secs(seconds))
+            .map(RelativeTime::At);
+    }
+    let offset = expression.strip_prefix("now").unwrap_or(expression);
+    if let Some(duration) = offset.strip_prefix('+') {
+        Some(RelativeTime::Later(parse_duration(duration)?))
+    } else if let Some(duration) = offset.strip_prefix('-') {
+        Some(RelativeTime::Earlier(parse_duration(duration)?))
+    } else {
+        None
+    }
+}
+
+impl Argument for RelativeTime {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let expression = parser.read_while(|c| !c.is_whitespace());
+        if expression == "in" {
+            parser.spacing()?;
+            let start = parser.branch();
+            let duration = parser.read_while(|c| !c.is_whitespace());
+            return parse_duration(duration)
+                .map(RelativeTime::Later)
+                .ok_or_else(|| start.error(ParseErrorKind::BadArgument));
+        }
+        if let Some(duration) = parse_duration(expression) {
+            let mut ago = parser.branch();
+            let followed_by_ago =
+                ago.spacing().is_ok() && ago.read_while(|c| !c.is_whitespace()) == "ago";
+            if followed_by_ago {
+                *parser = ago;
+                return Ok(RelativeTime::Earlier(duration));
+            }
+            return Ok(RelativeTime::Later(duration));
+        }
+        parse_relative(expression).ok_or_else(|| start.error(ParseErrorKind::BadArgument))
+    }
+
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        ["now", "tomorrow", "yesterday"].map(String::from).to_vec()
+    }
+}
+
+/// A day of the week, parsed from its English name or an abbreviation of at least three letters
+/// like `mon`, `tues` or `thurs`, ignoring case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// All days of the week, starting with Monday.
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    const NAMES: [&'static str; 7] = [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ];
+
+    /// The English name of the day, e.g. `Monday`.
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    /// The number of the day, from 1 for Monday to 7 for Sunday.
+    pub fn number_from_monday(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// The following day, wrapping around from Sunday to Monday.
+    pub fn succ(self) -> Self {
+        Self::ALL[(self as usize + 1) % 7]
+    }
+
+    /// The previous day, wrapping around from Monday to Sunday.
+    pub fn pred(self) -> Self {
+        Self::ALL[(self as usize + 6) % 7]
+    }
+}
+
+impl std::fmt::Display for Weekday {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Argument for Weekday {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        parse_name(parser, &Self::NAMES).map(|index| Self::ALL[index])
+    }
+
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        Self::NAMES.map(str::to_lowercase).to_vec()
+    }
+}
+
+/// A month of the year, parsed from its English name or an abbreviation of at least three letters
+/// like `jan` or `sept`, ignoring case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Month {
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+impl Month {
+    /// All months, starting with January.
+    pub const ALL: [Month; 12] = [
+        Month::January,
+        Month::February,
+        Month::March,
+        Month::April,
+        Month::May,
+        Month::June,
+        Month::July,
+        Month::August,
+        Month::September,
+        Month::October,
+        Month::November,
+        Month::December,
+    ];
+
+    const NAMES: [&'static str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+
+    /// The English name of the month, e.g. `January`.
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    /// The number of the month, from 1 for January to 12 for December.
+    pub fn number(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// The month with the given number from 1 to 12.
+    pub fn from_number(number: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(number).checked_sub(1)?).copied()
+    }
+
+    /// The following month, wrapping around from December to January.
+    pub fn succ(self) -> Self {
+        Self::ALL[(self as usize + 1) % 12]
+    }
+
+    /// The previous month, wrapping around from January to December.
+    pub fn pred(self) -> Self {
+        Self::ALL[(self as usize + 11) % 12]
+    }
+}
+
+impl std::fmt::Display for Month {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Argument for Month {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        parse_name(parser, &Self::NAMES).map(|index| Self::ALL[index])
+    }
+
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        Self::NAMES.map(str::to_lowercase).to_vec()
+    }
+}
+
+/// Parse one of `names`, ignoring case, or an abbreviation of at least three letters, returning its index.
+fn parse_name<'a>(parser: &mut CommandParser<'a>, names: &[&str]) -> Result<usize, ParseError<'a>> {
+    let start = parser.branch();
+    let word = parser.read_while(|c| c.is_ascii_alphabetic());
+    names
+        .iter()
+        .position(|name| {
+            word.len() >= 3
+                && word.len() <= name.len()
+                && name[..word.len()].eq_ignore_ascii_case(word)
+        })
+        .ok_or_else(|| start.error(ParseErrorKind::BadArgument))
+}
+
+/// Parses relative time expressions such as `now`, `now+2h`, `+1d` or `-30m`, resolved against the current time.
+#[cfg(feature = "system-time")]
+impl Argument for SystemTime {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let expression = parser.read_while(|c| !c.is_whitespace());
+        parse_relative(expression)
+            .and_then(RelativeTime::instant)
+            .ok_or_else(|| start.error(ParseErrorKind::BadArgument))
+    }
+}
@@ -0,0 +1,78 @@
+use std::{marker::PhantomData, ops::Deref};
+
+use url::Url;
+
+use crate::parser::{Argument, CommandParser, ParseError, ParseErrorKind};
+
+/// Parses a single absolute URL of any scheme.
+impl Argument for Url {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let url = parser.read_while(|c| !c.is_whitespace());
+        Url::parse(url).map_err(|_| start.error(ParseErrorKind::BadArgument))
+    }
+}
+
+/// An allow-list of URL schemes for use with `RestrictedUrl`.
+pub trait UrlSchemes {
+    /// The accepted schemes, in lowercase.
+    const SCHEMES: &'static [&'static str];
+}
+
+/// Allows `http` and `https` URLs.
+pub struct WebSchemes;
+
+impl UrlSchemes for WebSchemes {
+    const SCHEMES: &'static [&'static str] = &["http", "https"];
+}
+
+/// A URL whose scheme is restricted to the allow-list given by `S`.
+/// URLs with any other scheme are rejected while parsing.
+pub struct RestrictedUrl<S: UrlSchemes = WebSchemes> {
+    url: Url,
+    schemes: PhantomData<S>,
+}
+
+impl<S: UrlSchemes> RestrictedUrl<S> {
+    /// Unwrap the underlying URL.
+    pub fn into_inner(self) -> Url {
+        self.url
+    }
+}
+
+impl<S: UrlSchemes> Deref for RestrictedUrl<S> {
+    type Target = Url;
+
+    fn deref(&self) -> &Url {
+        &self.url
+    }
+}
+
+impl<S: UrlSchemes> std::fmt::Debug for RestrictedUrl<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.url.fmt(f)
+    }
+}
+
+impl<S: UrlSchemes> Argument for RestrictedUrl<S> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let url = parser.argument::<Url>()?;
+        if S::SCHEMES.contains(&url.scheme()) {
+            Ok(RestrictedUrl {
+                url,
+                schemes: PhantomData,
+            })
+        } else {
+            Err(start.error(ParseErrorKind::BadArgument))
+        }
+    }
+
+    /// Suggests the allowed schemes, e.g. `https://`.
+    fn suggestions(_parser: &CommandParser) -> Vec<String> {
+        S::SCHEMES
+            .iter()
+            .map(|scheme| format!("{}://", scheme))
+            .collect()
+    }
+}
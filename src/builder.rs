@@ -0,0 +1,113 @@
+//! Configuring a `CommandSource` in one place, see `CommandSource::builder`.
+
+use std::rc::Rc;
+
+use crate::{
+    dispatch::DispatchEvent, parser::ParserConfig, Builtin, CommandResult, CommandSource,
+    CommandUsage, DispatchHook, Fallback, LiteralProvider, Output, PermissionCheck,
+};
+
+/// Collects the configuration of a `CommandSource` before creating it.
+/// Commands themselves are registered on the built source using `register_command!`.
+pub struct CommandSourceBuilder<Context: 'static> {
+    context: Context,
+    config: ParserConfig,
+    builtins: Vec<Builtin>,
+    output: Option<Output>,
+    literals: Vec<(String, LiteralProvider<Context>)>,
+    permission_check: Option<PermissionCheck<Context>>,
+    fallback: Option<Fallback<Context>>,
+    dispatch_hook: Option<DispatchHook>,
+    suggestion_cache: Option<usize>,
+}
+
+impl<Context: 'static> CommandSourceBuilder<Context> {
+    pub(crate) fn new(context: Context) -> Self {
+        Self {
+            context,
+            config: ParserConfig::default(),
+            builtins: Vec::new(),
+            output: None,
+            literals: Vec::new(),
+            permission_check: None,
+            fallback: None,
+            dispatch_hook: None,
+            suggestion_cache: None,
+        }
+    }
+
+    /// Parse commands using the given configuration.
+    pub fn config(mut self, config: ParserConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Enable a command implemented by `oberst` itself, see `CommandSource::enable_builtin`.
+    pub fn builtin(mut self, builtin: Builtin) -> Self {
+        self.builtins.push(builtin);
+        self
+    }
+
+    /// Set where text written by built-in commands goes, see `CommandSource::set_output`.
+    pub fn output(mut self, output: impl Fn(&str) + 'static) -> Self {
+        self.output = Some(Rc::new(output));
+        self
+    }
+
+    /// Register a set of literals produced from the context, see `CommandSource::register_literals`.
+    pub fn literals(
+        mut self,
+        set: impl Into<String>,
+        provider: impl Fn(&Context) -> Vec<String> + 'static,
+    ) -> Self {
+        self.literals.push((set.into(), Rc::new(provider)));
+        self
+    }
+
+    /// Decide whether commands may be run, see `CommandSource::set_permission_check`.
+    pub fn permission_check(
+        mut self,
+        check: impl Fn(&Context, &CommandUsage) -> bool + 'static,
+    ) -> Self {
+        self.permission_check = Some(Rc::new(check));
+        self
+    }
+
+    /// Handle commands with unknown names, see `CommandSource::set_fallback`.
+    pub fn fallback(
+        mut self,
+        fallback: impl Fn(&Context, &str) -> CommandResult<'static> + 'static,
+    ) -> Self {
+        self.fallback = Some(Rc::new(fallback));
+        self
+    }
+
+    /// Observe every completed dispatch, see `CommandSource::set_dispatch_hook`.
+    pub fn dispatch_hook(mut self, hook: impl Fn(&DispatchEvent) + 'static) -> Self {
+        self.dispatch_hook = Some(Rc::new(hook));
+        self
+    }
+
+    /// Cache the suggestions for up to `capacity` inputs, see `CommandSource::enable_suggestion_cache`.
+    pub fn suggestion_cache(mut self, capacity: usize) -> Self {
+        self.suggestion_cache = Some(capacity);
+        self
+    }
+
+    /// Create the configured `CommandSource`.
+    pub fn build(self) -> CommandSource<Context> {
+        let source = CommandSource::with_config(self.context, self.config);
+        for builtin in self.builtins {
+            source.enable_builtin(builtin);
+        }
+        *source.output.borrow_mut() = self.output;
+        source.literals.borrow_mut().extend(self.literals);
+        *source.permission_check.borrow_mut() = self.permission_check;
+        *source.fallback.borrow_mut() = self.fallback;
+        *source.dispatch_hook.borrow_mut() = self.dispatch_hook;
+        if let Some(capacity) = self.suggestion_cache {
+            source.enable_suggestion_cache(capacity);
+        }
+        source
+    }
+}
@@ -0,0 +1,80 @@
+//! Splitting of input containing several commands, see `CommandSource::dispatch_chain`.
+
+/// How a command in a chain is connected to the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `;`: the command runs regardless of the outcome of the previous one.
+    Sequence,
+    /// `&&`: the command only runs if the previous one succeeded.
+    And,
+    /// `|`: the command only runs if the previous one succeeded and receives the text it wrote to the output as an additional, final argument.
+    /// Only recognized when it stands alone between whitespace, so words like `read|write` and `||` are left intact.
+    Pipe,
+}
+
+/// The separators recognized by default: `;` and `&&`, see `ParserConfig::chain_separators`.
+/// Piping is opt-in, e.g. by adding `("|".to_string(), Separator::Pipe)`.
+pub fn default_separators() -> Vec<(String, Separator)> {
+    vec![
+        (";".to_string(), Separator::Sequence),
+        ("&&".to_string(), Separator::And),
+    ]
+}
+
+/// Split the input at the given separators outside of quoted strings, preferring the longest separator at each position.
+/// Pipe separators only count when surrounded by whitespace or the ends of the input.
+/// Each command is returned along with the separator preceding it, if any.
+pub(crate) fn split<'i>(
+    input: &'i str,
+    separators: &[(String, Separator)],
+) -> Vec<(Option<Separator>, &'i str)> {
+    let mut commands = vec![];
+    let mut separator = None;
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut i = 0;
+    while let Some(c) = input[i..].chars().next() {
+        if quoted {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => {}
+            }
+            i += c.len_utf8();
+            continue;
+        }
+        if c == '"' {
+            quoted = true;
+            i += 1;
+            continue;
+        }
+        let next = separators
+            .iter()
+            .filter(|(text, separator)| {
+                !text.is_empty()
+                    && input[i..].starts_with(text.as_str())
+                    && (*separator != Separator::Pipe || stands_alone(input, i, text.len()))
+            })
+            .max_by_key(|(text, _)| text.len());
+        match next {
+            Some((text, next)) => {
+                commands.push((separator, input[start..i].trim()));
+                separator = Some(*next);
+                i += text.len();
+                start = i;
+            }
+            None => i += c.len_utf8(),
+        }
+    }
+    commands.push((separator, input[start..].trim()));
+    commands
+}
+
+/// Whether the `len` bytes at `start` are preceded and followed by whitespace or the ends of the input.
+fn stands_alone(input: &str, start: usize, len: usize) -> bool {
+    let before = input[..start].chars().next_back();
+    let after = input[start + len..].chars().next();
+    before.is_none_or(char::is_whitespace) && after.is_none_or(char::is_whitespace)
+}
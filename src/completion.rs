@@ -0,0 +1,267 @@
+//! Completion of partially typed commands, see `CommandSource::complete`.
+
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use crate::{
+    dynamic::ArgumentRegistry,
+    parser::{CommandParser, Expected},
+    ArgumentUsage, SyntaxNode, VariantUsage,
+};
+
+/// What a suggestion completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionKind {
+    /// The name of a registered command.
+    Command,
+    /// A literal keyword of a command variant.
+    Literal,
+    /// A possible value for an argument.
+    Value,
+    /// A placeholder like `<count: u32>` describing the expected argument.
+    /// Hints are meant to be displayed rather than inserted.
+    Hint,
+}
+
+/// A candidate completion for a partially typed command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The suggested text.
+    pub text: String,
+    /// The offset in bytes at which the suggested text starts, replacing everything after it.
+    pub start: usize,
+    pub kind: SuggestionKind,
+}
+
+impl Suggestion {
+    /// Create a suggestion of the given kind, replacing the input from `start` onwards.
+    pub fn new(text: impl Into<String>, start: usize, kind: SuggestionKind) -> Self {
+        Self {
+            text: text.into(),
+            start,
+            kind,
+        }
+    }
+}
+
+/// How suggestions are ordered, see `ParserConfig::suggestion_ranking`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SuggestionRanking {
+    /// Command names in alphabetical order and everything else in the order of the command's syntax.
+    /// On an empty prompt, recently and frequently used commands come first, see `CommandSource::recent_commands`.
+    #[default]
+    Natural,
+    /// All suggestions in alphabetical order.
+    Alphabetical,
+    /// Command names by how often they were dispatched, see `CommandSource::usage_count`.
+    Frequency,
+    /// Command names containing the typed characters in order, such as `tp` for `teleport`,
+    /// with prefixes ranked first, followed by names containing the typed text and all others.
+    Fuzzy,
+}
+
+/// Score how well `candidate` matches the typed text for `SuggestionRanking::Fuzzy`, where higher is better.
+/// Returns `None` if the candidate doesn't contain the typed characters in order.
+pub(crate) fn fuzzy_score(candidate: &str, typed: &str) -> Option<u8> {
+    if candidate.starts_with(typed) {
+        return Some(2);
+    }
+    if candidate.contains(typed) {
+        return Some(1);
+    }
+    let mut chars = candidate.chars();
+    typed
+        .chars()
+        .all(|c| chars.any(|candidate| candidate == c))
+        .then_some(0)
+}
+
+/// Measurements of a single completion, see `CommandSource::complete_with_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionStats {
+    /// How long it took to produce the suggestions.
+    pub duration: Duration,
+    /// Whether the suggestions were taken from the suggestion cache.
+    pub cached: bool,
+}
+
+/// Suggestions produced for previous inputs, see `CommandSource::enable_suggestion_cache`.
+pub(crate) struct SuggestionCache {
+    capacity: usize,
+    entries: HashMap<String, Vec<Suggestion>>,
+}
+
+impl SuggestionCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn get(&self, partial: &str) -> Option<Vec<Suggestion>> {
+        self.entries.get(partial).cloned()
+    }
+
+    /// Store the suggestions for `partial`, starting over once the cache is full.
+    pub(crate) fn insert(&mut self, partial: &str, suggestions: Vec<Suggestion>) {
+        if self.entries.len() >= self.capacity {
+            self.entries.clear();
+        }
+        self.entries.insert(partial.to_string(), suggestions);
+    }
+
+    pub(crate) fn remove(&mut self, partial: &str) {
+        self.entries.remove(partial);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// The number of recently dispatched commands remembered by `UsageStats`.
+const RECENT_COMMANDS: usize = 5;
+
+/// How often and how recently commands were dispatched, see `CommandSource::usage_count`.
+#[derive(Default)]
+pub(crate) struct UsageStats {
+    counts: HashMap<&'static str, u64>,
+    /// The most recently dispatched commands, most recent first.
+    recent: VecDeque<&'static str>,
+}
+
+impl UsageStats {
+    pub(crate) fn record(&mut self, command: &'static str) {
+        *self.counts.entry(command).or_default() += 1;
+        self.recent.retain(|recent| *recent != command);
+        self.recent.push_front(command);
+        self.recent.truncate(RECENT_COMMANDS);
+    }
+
+    pub(crate) fn count(&self, command: &str) -> u64 {
+        self.counts.get(command).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn recent(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.recent.iter().copied()
+    }
+
+    /// Order command names for an empty prompt: recently used ones first, followed by the most used ones.
+    /// Names which were never dispatched keep their order.
+    pub(crate) fn rank(&self, names: &mut [&str]) {
+        names.sort_by_key(|name| {
+            let recent = self.recent.iter().position(|recent| recent == name);
+            (recent.is_none(), recent, Reverse(self.count(name)))
+        });
+    }
+}
+
+/// Walk the syntax of a variant as far as the input allows,
+/// then suggest whatever the variant expects at the end of the input.
+/// Arguments whose type resolves to a kind with a suggestion provider set with `ArgumentRegistry::register_suggestions`
+/// are completed using it instead of their own suggestions.
+pub(crate) fn complete_variant(
+    variant: &VariantUsage,
+    mut parser: CommandParser<'_>,
+    registry: &ArgumentRegistry,
+) -> Vec<Suggestion> {
+    for node in variant.syntax {
+        if parser.spacing().is_err() {
+            // The previous token is still being typed, which is handled by the previous node
+            break;
+        }
+        let start = parser.offset();
+        let rest = parser.remaining();
+        // Whether the rest of the input is a single token which might still be incomplete
+        let last_token = !rest.contains(char::is_whitespace);
+
+        match node {
+            SyntaxNode::Literal(literal) => {
+                if last_token && literal.starts_with(rest) {
+                    return vec![Suggestion::new(*literal, start, SuggestionKind::Literal)];
+                } else if parser.lit(literal).is_err() {
+                    break;
+                }
+            }
+            SyntaxNode::Argument(argument) => {
+                let mut branch = parser.branch();
+                if !last_token && (argument.parse)(&mut branch).is_ok() {
+                    parser = branch;
+                } else if last_token {
+                    let hint = argument_hint(argument);
+                    let hint = if argument.optional {
+                        format!("[{}]", hint)
+                    } else {
+                        hint
+                    };
+                    let mut suggestions = vec![Suggestion::new(hint, start, SuggestionKind::Hint)];
+                    let suggest = registry
+                        .custom_suggestions(argument.ty)
+                        .unwrap_or(argument.suggest);
+                    suggestions.extend(
+                        suggest(&parser)
+                            .into_iter()
+                            .filter(|value| value.starts_with(rest))
+                            .map(|value| Suggestion::new(value, start, SuggestionKind::Value)),
+                    );
+                    return suggestions;
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Describe an argument like `<count: u32>`.
+fn argument_hint(argument: &ArgumentUsage) -> String {
+    format!("<{}: {}>", argument.name, argument.ty)
+}
+
+/// Walk the syntax of a variant as far as the input allows
+/// and return what the variant expects where it fails, along with the offset.
+/// Returns `None` if the variant matches the whole input.
+pub(crate) fn expect_variant(
+    variant: &VariantUsage,
+    mut parser: CommandParser<'_>,
+) -> Option<(usize, Vec<Expected>)> {
+    for node in variant.syntax {
+        let mut branch = parser.branch();
+        let spaced = branch.spacing().is_ok();
+        let at = branch.offset();
+        let (item, parsed, optional) = match node {
+            SyntaxNode::Literal(literal) => (
+                Expected::Literal(literal.to_string()),
+                spaced && branch.lit(literal).is_ok(),
+                false,
+            ),
+            SyntaxNode::Argument(argument) => (
+                Expected::Argument(argument_hint(argument)),
+                spaced && (argument.parse)(&mut branch).is_ok(),
+                argument.optional,
+            ),
+        };
+        if !parsed {
+            let mut items = vec![item];
+            if optional {
+                items.push(Expected::End);
+            }
+            return Some((at, items));
+        }
+        parser = branch;
+    }
+    parser.read_while(char::is_whitespace);
+    parser
+        .end()
+        .is_err()
+        .then(|| (parser.offset(), vec![Expected::End]))
+}
@@ -0,0 +1,174 @@
+//! Options for individual dispatches and observing their outcome, see `CommandSource::dispatch_with`.
+
+use std::collections::BTreeMap;
+
+use crate::{CommandError, CommandResult};
+
+/// Options for dispatching a single command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DispatchOptions {
+    /// An identifier tying the dispatch to whatever caused it, e.g. a chat message or a request from another service.
+    /// It is passed to the dispatch hook, recorded in the tracing span of the dispatch and returned along with the result.
+    pub correlation_id: Option<String>,
+    /// The engine flags stripped from the end of the command, see `ParserConfig::engine_flags`,
+    /// mapped to their value or `None` if they don't take one.
+    pub flags: BTreeMap<String, Option<String>>,
+}
+
+impl DispatchOptions {
+    /// Options with the given correlation id.
+    pub fn correlated(correlation_id: impl Into<String>) -> Self {
+        Self {
+            correlation_id: Some(correlation_id.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the engine flag `name` was given, e.g. `--silent`.
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    /// The value given for the engine flag `name`, e.g. the player in `--as Steve`.
+    pub fn flag_value(&self, name: &str) -> Option<&str> {
+        self.flags.get(name)?.as_deref()
+    }
+}
+
+/// A flag any command may be followed by, which is handled by the dispatcher instead of the command's grammar,
+/// see `ParserConfig::engine_flags`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EngineFlag {
+    /// The flag as it is written, including its dashes, e.g. `--silent`.
+    pub name: String,
+    /// Whether the flag is followed by a value, like the player in `--as Steve`.
+    pub takes_value: bool,
+}
+
+impl EngineFlag {
+    /// A flag without a value, e.g. `--silent`.
+    pub fn switch(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            takes_value: false,
+        }
+    }
+
+    /// A flag followed by a single value, e.g. `--as <player>`.
+    pub fn valued(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            takes_value: true,
+        }
+    }
+}
+
+/// Split the last whitespace-separated token off the command.
+fn last_token(command: &str) -> Option<(&str, &str)> {
+    let command = command.trim_end();
+    let start = command.rfind(char::is_whitespace)? + 1;
+    Some((&command[..start], &command[start..]))
+}
+
+/// Whether the token is a negative number like `-5` or `-0.5`, which is never taken for a flag.
+fn is_negative_number(token: &str) -> bool {
+    token.strip_prefix('-').is_some_and(|number| {
+        number.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+            && number.parse::<f64>().is_ok()
+    })
+}
+
+/// Strip the engine flags trailing the command, recording them in `options`.
+/// The name of the command itself is never taken for a flag or its value.
+/// Stripping stops at a standalone `--`, which is removed as well.
+pub(crate) fn strip_flags<'c>(
+    mut command: &'c str,
+    flags: &[EngineFlag],
+    options: &mut DispatchOptions,
+) -> &'c str {
+    if flags.is_empty() {
+        return command;
+    }
+    while let Some((rest, token)) = last_token(command) {
+        if token == "--" {
+            command = rest;
+            break;
+        }
+        if let Some(flag) = flags
+            .iter()
+            .find(|flag| !flag.takes_value && flag.name == token && !is_negative_number(token))
+        {
+            options.flags.entry(flag.name.clone()).or_insert(None);
+            command = rest;
+            continue;
+        }
+        let valued = last_token(rest).and_then(|(rest, name)| {
+            let flag = flags
+                .iter()
+                .find(|flag| flag.takes_value && flag.name == name && !is_negative_number(name))?;
+            Some((rest, flag))
+        });
+        match valued {
+            Some((rest, flag)) => {
+                options
+                    .flags
+                    .entry(flag.name.clone())
+                    .or_insert(Some(token.to_string()));
+                command = rest;
+            }
+            None => break,
+        }
+    }
+    command.trim_end()
+}
+
+/// The outcome of `CommandSource::dispatch_with`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Dispatched<'a> {
+    pub result: CommandResult<'a>,
+    /// The correlation id given in the `DispatchOptions`.
+    pub correlation_id: Option<String>,
+    /// The engine flags stripped from the command, see `DispatchOptions::flags`.
+    pub flags: BTreeMap<String, Option<String>>,
+}
+
+/// A completed dispatch as seen by the dispatch hook, see `CommandSource::set_dispatch_hook`.
+#[derive(Debug, Clone, Copy)]
+pub struct DispatchEvent<'e> {
+    /// The command as it was dispatched.
+    pub command: &'e str,
+    pub correlation_id: Option<&'e str>,
+    /// The engine flags stripped from the command, see `DispatchOptions::flags`.
+    pub flags: &'e BTreeMap<String, Option<String>>,
+    pub result: Result<i32, &'e CommandError<'e>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strip_flags, DispatchOptions, EngineFlag};
+
+    fn strip<'c>(command: &'c str, flags: &[EngineFlag]) -> (&'c str, Vec<String>) {
+        let mut options = DispatchOptions::default();
+        let command = strip_flags(command, flags, &mut options);
+        (command, options.flags.into_keys().collect())
+    }
+
+    #[test]
+    fn flags_and_negative_numbers() {
+        let flags = [
+            EngineFlag::switch("--silent"),
+            EngineFlag::switch("-1"),
+            EngineFlag::valued("--as"),
+        ];
+        assert_eq!(
+            strip("move -5 --silent --as Steve", &flags),
+            ("move -5", vec!["--as".to_string(), "--silent".to_string()])
+        );
+        assert_eq!(strip("move -1", &flags), ("move -1", vec![]));
+        assert_eq!(
+            strip("move --silent -- --as Steve", &flags),
+            ("move --silent", vec!["--as".to_string()])
+        );
+        assert_eq!(strip("move -5 --", &flags), ("move -5", vec![]));
+    }
+}
@@ -0,0 +1,375 @@
+//! Type-erased argument parsing for syntax that is only known at runtime,
+//! e.g. a `DynamicSyntax` built from a usage string that refers to argument kinds by name.
+
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+    },
+};
+
+use crate::{
+    completion::{Suggestion, SuggestionKind},
+    parser::{Argument, CommandParser, ParseError, ParseErrorKind},
+};
+
+/// A type-erased argument parser.
+pub type DynamicParse = for<'a> fn(&mut CommandParser<'a>) -> Result<Box<dyn Any>, ParseError<'a>>;
+
+/// Parse an `A` and box it.
+fn parse_boxed<'a, A: Argument + 'static>(
+    parser: &mut CommandParser<'a>,
+) -> Result<Box<dyn Any>, ParseError<'a>> {
+    parser
+        .argument::<A>()
+        .map(|argument| Box::new(argument) as Box<dyn Any>)
+}
+
+/// A type-erased suggestion provider, returning candidate completions for an argument at the parser's position.
+pub type DynamicSuggest = for<'a> fn(&CommandParser<'a>) -> Vec<String>;
+
+/// The parser and suggestion provider registered for an argument kind.
+#[derive(Clone, Copy)]
+pub struct ArgumentKind {
+    pub parse: DynamicParse,
+    pub suggest: Option<DynamicSuggest>,
+}
+
+/// Maps argument kind names such as `"player"` or `"u32"` to their parsers and suggestion providers,
+/// for parsing syntax that refers to argument kinds by name at runtime.
+/// Each `CommandSource` owns a registry that is pre-populated with the types supported by `oberst` itself.
+/// The source resolves the types of its commands' arguments to kinds through it, see `kind_of`:
+/// completion uses suggestion providers set with `register_suggestions` in place of the arguments' own suggestions,
+/// and `CommandSource::schema` includes the kinds, so clients can parse arguments by the same names.
+#[derive(Default, Clone)]
+pub struct ArgumentRegistry {
+    kinds: HashMap<String, ArgumentKind>,
+    /// The kinds whose suggestion provider was set with `register_suggestions`.
+    custom_suggestions: HashSet<String>,
+    /// The kinds registered with `register_type`, by the name of their type without module paths or whitespace.
+    types: HashMap<String, String>,
+}
+
+/// Remove module paths and whitespace from a type name, e.g. `alloc::vec::Vec<u32>` becomes `Vec<u32>`.
+fn normalize_type_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("::") {
+            let start = normalized
+                .rfind(|c: char| !c.is_alphanumeric() && c != '_')
+                .map_or(0, |i| i + 1);
+            normalized.truncate(start);
+            rest = after;
+            continue;
+        }
+        if !c.is_whitespace() {
+            normalized.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    normalized
+}
+
+macro_rules! register_builtin {
+    ($registry:expr, $($t:ty),*) => {
+        $(
+            $registry.register_type::<$t>(stringify!($t));
+        )*
+    };
+}
+
+impl ArgumentRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry containing the basic types implemented in the `parser` module,
+    /// registered under their type names, e.g. `"u32"` or `"String"`.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        register_builtin!(
+            registry,
+            u8,
+            u16,
+            u32,
+            u64,
+            u128,
+            usize,
+            i8,
+            i16,
+            i32,
+            i64,
+            i128,
+            isize,
+            f32,
+            f64,
+            bool,
+            char,
+            String,
+            NonZeroU8,
+            NonZeroU16,
+            NonZeroU32,
+            NonZeroU64,
+            NonZeroU128,
+            NonZeroUsize,
+            NonZeroI8,
+            NonZeroI16,
+            NonZeroI32,
+            NonZeroI64,
+            NonZeroI128,
+            NonZeroIsize
+        );
+        registry
+    }
+
+    /// Register a parser for the given kind name, replacing any previous parser and suggestion provider.
+    pub fn register(&mut self, kind: impl Into<String>, parser: DynamicParse) {
+        let kind = kind.into();
+        self.types.retain(|_, registered| *registered != kind);
+        self.custom_suggestions.remove(&kind);
+        self.kinds.insert(
+            kind,
+            ArgumentKind {
+                parse: parser,
+                suggest: None,
+            },
+        );
+    }
+
+    /// Register the `Argument` implementation of `A` under the given kind name, including its suggestions.
+    /// Arguments of type `A` are resolved to this kind by `kind_of`.
+    pub fn register_type<A: Argument + 'static>(&mut self, kind: impl Into<String>) {
+        let kind = kind.into();
+        self.types.insert(
+            normalize_type_name(std::any::type_name::<A>()),
+            kind.clone(),
+        );
+        self.custom_suggestions.remove(&kind);
+        self.kinds.insert(
+            kind,
+            ArgumentKind {
+                parse: parse_boxed::<A>,
+                suggest: Some(A::suggestions),
+            },
+        );
+    }
+
+    /// Set the suggestion provider of an already registered kind.
+    /// Returns `false` if there is no kind with the given name.
+    pub fn register_suggestions(&mut self, kind: &str, suggest: DynamicSuggest) -> bool {
+        match self.kinds.get_mut(kind) {
+            Some(entry) => {
+                entry.suggest = Some(suggest);
+                self.custom_suggestions.insert(kind.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the parser and suggestion provider registered for the given kind name.
+    pub fn get(&self, kind: &str) -> Option<ArgumentKind> {
+        self.kinds.get(kind).copied()
+    }
+
+    /// Resolve the type of an argument as it is written in a command, e.g. `"u32"` or `"Player"`, to a registered kind.
+    /// Types registered with `register_type` resolve to their kind, e.g. `"player"` for `register_type::<Player>("player")`,
+    /// while other types only resolve to a kind registered under the type's name itself.
+    pub fn kind_of(&self, ty: &str) -> Option<&str> {
+        if let Some((kind, _)) = self.kinds.get_key_value(ty) {
+            return Some(kind);
+        }
+        let kind = self.types.get(&normalize_type_name(ty))?;
+        self.kinds.contains_key(kind).then_some(kind.as_str())
+    }
+
+    /// The suggestion provider set with `register_suggestions` for the kind an argument of the type named `ty` resolves to.
+    pub(crate) fn custom_suggestions(&self, ty: &str) -> Option<DynamicSuggest> {
+        let kind = self.kind_of(ty)?;
+        if !self.custom_suggestions.contains(kind) {
+            return None;
+        }
+        self.kinds.get(kind)?.suggest
+    }
+
+    /// Iterate over the names of all registered kinds.
+    pub fn kinds(&self) -> impl Iterator<Item = &str> {
+        self.kinds.keys().map(String::as_str)
+    }
+
+    /// Parse an argument of the given kind.
+    /// Unknown kinds are reported as a `BadArgument` error.
+    pub fn parse<'a>(
+        &self,
+        kind: &str,
+        parser: &mut CommandParser<'a>,
+    ) -> Result<Box<dyn Any>, ParseError<'a>> {
+        match self.get(kind) {
+            Some(entry) => (entry.parse)(parser),
+            None => Err(parser.error(ParseErrorKind::BadArgument)),
+        }
+    }
+
+    /// Get suggestions for an argument of the given kind at the parser's position.
+    /// Returns nothing for unknown kinds or kinds without a suggestion provider.
+    pub fn suggest(&self, kind: &str, parser: &CommandParser) -> Vec<String> {
+        self.get(kind)
+            .and_then(|entry| entry.suggest)
+            .map(|suggest| suggest(parser))
+            .unwrap_or_default()
+    }
+}
+
+/// Split a usage string at whitespace, keeping `<...>` segments together.
+fn split_usage(usage: &str) -> Vec<&str> {
+    let mut segments = vec![];
+    let mut start = None;
+    let mut depth = 0;
+    for (i, c) in usage.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            c if c.is_whitespace() && depth <= 0 => {
+                if let Some(start) = start.take() {
+                    segments.push(&usage[start..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(start) = start {
+        segments.push(&usage[start..]);
+    }
+    segments
+}
+
+enum DynamicNode {
+    Literal(String),
+    Argument { name: String, kind: String },
+}
+
+/// A command syntax built at runtime from a usage string like `give <target: player> <count: u32>`.
+/// Argument kinds are resolved through an `ArgumentRegistry` while parsing.
+/// Use `CommandSource::register_dynamic` to dispatch and complete commands with this syntax.
+pub struct DynamicSyntax {
+    nodes: Vec<DynamicNode>,
+}
+
+impl DynamicSyntax {
+    /// Build the syntax described by the given usage string.
+    /// Returns `None` if an argument is not of the form `<name: kind>`.
+    pub fn new(usage: &str) -> Option<Self> {
+        split_usage(usage)
+            .into_iter()
+            .map(|segment| {
+                if let Some(argument) = segment
+                    .strip_prefix('<')
+                    .and_then(|segment| segment.strip_suffix('>'))
+                {
+                    let (name, kind) = argument.split_once(':')?;
+                    Some(DynamicNode::Argument {
+                        name: name.trim().to_string(),
+                        kind: kind.trim().to_string(),
+                    })
+                } else {
+                    Some(DynamicNode::Literal(segment.to_string()))
+                }
+            })
+            .collect::<Option<_>>()
+            .map(|nodes| Self { nodes })
+    }
+
+    /// Parse the syntax from the parser's current position, collecting all arguments by name.
+    /// Like generated commands, each node has to be preceded by whitespace.
+    pub fn parse<'a>(
+        &self,
+        registry: &ArgumentRegistry,
+        parser: &mut CommandParser<'a>,
+    ) -> Result<DynamicArguments, ParseError<'a>> {
+        let mut arguments = HashMap::new();
+        for node in &self.nodes {
+            parser.spacing()?;
+            match node {
+                DynamicNode::Literal(literal) => parser.lit(literal)?,
+                DynamicNode::Argument { name, kind } => {
+                    arguments.insert(name.clone(), registry.parse(kind, parser)?);
+                }
+            }
+        }
+        parser.end()?;
+        Ok(DynamicArguments(arguments))
+    }
+
+    /// Walk the syntax as far as the input allows, then suggest whatever it expects at the end of the input,
+    /// like the variants of generated commands.
+    pub fn complete(
+        &self,
+        registry: &ArgumentRegistry,
+        mut parser: CommandParser,
+    ) -> Vec<Suggestion> {
+        for node in &self.nodes {
+            if parser.spacing().is_err() {
+                break;
+            }
+            let start = parser.offset();
+            let rest = parser.remaining();
+            let last_token = !rest.contains(char::is_whitespace);
+
+            match node {
+                DynamicNode::Literal(literal) => {
+                    if last_token && literal.starts_with(rest) {
+                        return vec![Suggestion::new(literal, start, SuggestionKind::Literal)];
+                    } else if parser.lit(literal).is_err() {
+                        break;
+                    }
+                }
+                DynamicNode::Argument { name, kind } => {
+                    if last_token {
+                        let hint = format!("<{}: {}>", name, kind);
+                        let mut suggestions =
+                            vec![Suggestion::new(hint, start, SuggestionKind::Hint)];
+                        suggestions.extend(
+                            registry
+                                .suggest(kind, &parser)
+                                .into_iter()
+                                .filter(|value| value.starts_with(rest))
+                                .map(|value| Suggestion::new(value, start, SuggestionKind::Value)),
+                        );
+                        return suggestions;
+                    } else if registry.parse(kind, &mut parser).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// The arguments parsed by a `DynamicSyntax`, keyed by name.
+pub struct DynamicArguments(HashMap<String, Box<dyn Any>>);
+
+impl DynamicArguments {
+    /// Get the argument with the given name if it exists and is of type `T`.
+    pub fn get<T: 'static>(&self, name: &str) -> Option<&T> {
+        self.0.get(name)?.downcast_ref()
+    }
+
+    /// Take ownership of the argument with the given name if it exists and is of type `T`.
+    pub fn take<T: 'static>(&mut self, name: &str) -> Option<T> {
+        if !self.0.get(name)?.is::<T>() {
+            return None;
+        }
+        self.0
+            .remove(name)?
+            .downcast()
+            .ok()
+            .map(|argument| *argument)
+    }
+}
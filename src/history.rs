@@ -0,0 +1,112 @@
+//! The commands dispatched by a source, see `CommandSource::history`.
+
+use std::{borrow::Cow, collections::VecDeque};
+
+/// The most recent commands along with their numbers, which keep counting up once old entries are dropped.
+#[derive(Default)]
+pub(crate) struct History {
+    entries: VecDeque<String>,
+    /// The number of the oldest entry still remembered, starting at 1.
+    first: usize,
+}
+
+impl History {
+    /// Remember a command, dropping the oldest ones beyond `capacity`. Blank commands are ignored.
+    pub(crate) fn record(&mut self, command: &str, capacity: usize) {
+        if command.trim().is_empty() || capacity == 0 {
+            return;
+        }
+        self.entries.push_back(command.to_string());
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+            self.first += 1;
+        }
+    }
+
+    /// The remembered commands along with their numbers, oldest first.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (usize, &str)> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (self.first + i + 1, entry.as_str()))
+    }
+
+    fn last(&self) -> Option<&str> {
+        self.entries.back().map(String::as_str)
+    }
+
+    fn get(&self, number: usize) -> Option<&str> {
+        let index = number.checked_sub(self.first + 1)?;
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Replace `!!` with the previous command and `!n` with the command numbered `n` outside of quoted strings.
+    /// References are only expanded at the start of a token, so `wow!!` is kept as-is, as is a `!` followed by anything else.
+    /// `\!` stands for a `!` that is never expanded.
+    /// Fails with the offset and text of the first reference to a command that isn't remembered.
+    pub(crate) fn expand<'c>(&self, command: &'c str) -> Result<Cow<'c, str>, (usize, &'c str)> {
+        let mut expanded = String::new();
+        // The part of the command already copied to `expanded`
+        let mut copied = 0;
+        let mut quoted = false;
+        let mut escaped = false;
+        let mut i = 0;
+        while let Some(c) = command[i..].chars().next() {
+            if quoted {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => quoted = false,
+                    _ => {}
+                }
+                i += c.len_utf8();
+                continue;
+            }
+            match c {
+                '"' => quoted = true,
+                '\\' if command[i + 1..].starts_with('!') => {
+                    // Drop the backslash and keep the `!` as-is
+                    expanded.push_str(&command[copied..i]);
+                    copied = i + 1;
+                    i += 2;
+                    continue;
+                }
+                '!' if command[..i]
+                    .chars()
+                    .next_back()
+                    .is_none_or(char::is_whitespace) =>
+                {
+                    let rest = &command[i + 1..];
+                    let (len, entry) = if rest.starts_with('!') {
+                        (2, self.last())
+                    } else {
+                        let digits = rest.len()
+                            - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                        if digits == 0 {
+                            i += 1;
+                            continue;
+                        }
+                        (
+                            1 + digits,
+                            rest[..digits].parse().ok().and_then(|n| self.get(n)),
+                        )
+                    };
+                    let entry = entry.ok_or((i, &command[i..i + len]))?;
+                    expanded.push_str(&command[copied..i]);
+                    expanded.push_str(entry);
+                    i += len;
+                    copied = i;
+                    continue;
+                }
+                _ => {}
+            }
+            i += c.len_utf8();
+        }
+        if copied == 0 {
+            Ok(Cow::Borrowed(command))
+        } else {
+            expanded.push_str(&command[copied..]);
+            Ok(Cow::Owned(expanded))
+        }
+    }
+}
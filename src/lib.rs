@@ -1,7 +1,27 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    borrow::Cow,
+    cell::{Ref, RefCell, RefMut},
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    panic::Location,
+    rc::Rc,
+    time::Instant,
+};
 
+pub mod arguments;
+pub mod builder;
+pub mod chain;
+pub mod completion;
+pub mod dispatch;
+pub mod dynamic;
+mod history;
 pub mod parser;
-pub use oberst_proc::define_command;
+pub mod render;
+pub mod schema;
+#[cfg(feature = "async")]
+pub mod shutdown;
+pub use oberst_proc::{define_command, Argument, Collection, Settings};
 
 /// Helper type used internally by `define_command!`.
 pub type Parse<Context> = for<'a> fn(
@@ -9,18 +29,140 @@ pub type Parse<Context> = for<'a> fn(
 ) -> Result<Execute<'a, Context>, parser::ParseError<'a>>;
 
 /// Helper type used internally by `define_command!`.
-pub type Execute<'a, Context> = Box<dyn FnOnce(&Context) -> CommandResult<'a>>;
+/// Holds a parsed command variant, ready to be executed.
+pub enum Execute<'a, Context> {
+    Sync(Box<dyn FnOnce(&Context) -> CommandResult<'a>>),
+    /// A variant taking a mutable reference to the context.
+    Mut(Box<dyn FnOnce(&mut Context) -> CommandResult<'a>>),
+    /// A variant defined as an `async fn`.
+    #[cfg(feature = "async")]
+    Async(Box<dyn for<'c> FnOnce(&'c Context) -> CommandFuture<'c, 'a>>),
+}
+
+#[cfg(feature = "async")]
+impl<'a, Context> Execute<'a, Context> {
+    /// Helper function used by `define_command!` to ensure the closure borrows the context for the lifetime of its future.
+    pub fn from_async<F>(execute: F) -> Self
+    where
+        F: for<'c> FnOnce(&'c Context) -> CommandFuture<'c, 'a> + 'static,
+    {
+        Execute::Async(Box::new(execute))
+    }
+}
+
+/// The future returned by async command variants.
+#[cfg(feature = "async")]
+pub type CommandFuture<'c, 'a> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = CommandResult<'a>> + 'c>>;
 
 /// Any error that can occur while parsing or executing a command.
+/// Errors returned by commands can't be compared by value, so they are compared by their message instead.
 #[derive(Debug)]
 pub enum CommandError<'a> {
     Parse(parser::ParseError<'a>),
+    /// An error returned by the command, see `CommandError::dispatch`.
     Dispatch(Box<dyn std::error::Error + 'a>),
+    /// The command variant mutates the context and has to be run using `CommandSource::dispatch_mut`.
+    RequiresMutableContext,
+    /// The context is already borrowed by a command that is still running, e.g. when dispatching from within a command.
+    ContextBorrowed,
+    /// The context has been torn down by the host, see `CommandSource::with_context_accessor`.
+    ContextUnavailable,
+    /// The command was registered without any variants.
+    NoVariants,
+    /// The permission check rejected the command, see `CommandSource::set_permission_check`.
+    PermissionDenied,
+    /// The command variant is asynchronous and has to be run using `CommandSource::dispatch_async`.
+    /// This and the following variants only occur with the `async` feature, but always exist so that matches
+    /// don't depend on the features enabled by other crates.
+    RequiresAsync,
+    /// The source is shutting down and no longer accepts commands, see `CommandSource::shutdown`.
+    ShuttingDown,
+    /// The command was still running when shutting down timed out, see `CommandSource::shutdown`.
+    Cancelled,
+}
+
+impl Display for CommandError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Parse(error) => error.fmt(f),
+            CommandError::Dispatch(error) => error.fmt(f),
+            CommandError::RequiresMutableContext => {
+                write!(f, "The command requires mutable access to its context")
+            }
+            CommandError::ContextBorrowed => {
+                write!(f, "The context is already in use by another command")
+            }
+            CommandError::ContextUnavailable => write!(f, "The context is no longer available"),
+            CommandError::NoVariants => write!(f, "The command has no variants"),
+            CommandError::PermissionDenied => {
+                write!(f, "You do not have permission to run this command")
+            }
+            CommandError::RequiresAsync => write!(f, "The command has to be run asynchronously"),
+            CommandError::ShuttingDown => write!(f, "No more commands are accepted"),
+            CommandError::Cancelled => write!(f, "The command was cancelled"),
+        }
+    }
 }
 
+impl PartialEq for CommandError<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (CommandError::Parse(error), CommandError::Parse(other)) => error == other,
+            (CommandError::Dispatch(error), CommandError::Dispatch(other)) => {
+                error.to_string() == other.to_string()
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for CommandError<'_> {}
+
 /// The result of a command execution.
 pub type CommandResult<'a> = std::result::Result<i32, CommandError<'a>>;
 
+impl<'a> CommandError<'a> {
+    /// An error returned by a command, e.g. `CommandError::dispatch("There is no such player")`.
+    pub fn dispatch(error: impl Into<Box<dyn std::error::Error + 'a>>) -> Self {
+        CommandError::Dispatch(error.into())
+    }
+
+    /// Convert the error into one that no longer borrows the command.
+    /// Errors returned by commands are replaced with their message.
+    pub fn into_owned(self) -> CommandError<'static> {
+        match self {
+            CommandError::Parse(error) => CommandError::Parse(error.into_owned()),
+            CommandError::Dispatch(error) => CommandError::dispatch(error.to_string()),
+            CommandError::RequiresMutableContext => CommandError::RequiresMutableContext,
+            CommandError::ContextBorrowed => CommandError::ContextBorrowed,
+            CommandError::ContextUnavailable => CommandError::ContextUnavailable,
+            CommandError::NoVariants => CommandError::NoVariants,
+            CommandError::PermissionDenied => CommandError::PermissionDenied,
+            CommandError::RequiresAsync => CommandError::RequiresAsync,
+            CommandError::ShuttingDown => CommandError::ShuttingDown,
+            CommandError::Cancelled => CommandError::Cancelled,
+        }
+    }
+
+    /// A stable, machine-readable code identifying the error, e.g. `permission_denied`.
+    /// Parse errors use the code of their kind, see `ParseErrorKind::code`, while errors returned by commands use `dispatch`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::Parse(error) => error.code(),
+            CommandError::Dispatch(_) => "dispatch",
+            CommandError::RequiresMutableContext => "requires_mutable_context",
+            CommandError::ContextBorrowed => "context_borrowed",
+            CommandError::ContextUnavailable => "context_unavailable",
+            CommandError::NoVariants => "no_variants",
+            CommandError::PermissionDenied => "permission_denied",
+            CommandError::RequiresAsync => "requires_async",
+            CommandError::ShuttingDown => "shutting_down",
+            CommandError::Cancelled => "cancelled",
+        }
+    }
+}
+
 impl<'a, E> From<E> for CommandError<'a>
 where
     E: std::error::Error + 'a,
@@ -30,98 +172,1798 @@ where
     }
 }
 
+/// Runs a command registered with `CommandSource::register_dynamic` using the arguments parsed by its syntax.
+pub type DynamicExecute<Context> =
+    Rc<dyn Fn(&Context, dynamic::DynamicArguments) -> CommandResult<'static>>;
+
+/// Cut off `text` with `…` if it is longer than `width` characters.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    match width.checked_sub(1) {
+        Some(kept) => text.chars().take(kept).chain(['…']).collect(),
+        None => String::new(),
+    }
+}
+
 /// Contains the name and possible usages of a command.
 /// Generated automatically.
 #[derive(Clone)]
 pub struct CommandUsage {
     pub name: &'static str,
+    /// A stable key identifying the command for localization, e.g. `commands.teleport`.
+    pub key: &'static str,
     pub usage: &'static [&'static str],
     pub description: Option<&'static str>,
+    pub variants: &'static [VariantUsage],
 }
 
+impl CommandUsage {
+    /// Render a help text listing the command's variants along with the help text of their arguments.
+    pub fn help(&self) -> String {
+        self.render(usize::MAX)
+    }
+
+    /// Render the help text like `help`, fitting each line into `width` characters, e.g. 80 for a console.
+    /// Usages too long for a line are wrapped between their parts, while descriptions and help texts are cut off with `…`.
+    pub fn render(&self, width: usize) -> String {
+        let mut header = self.name.to_string();
+        if let Some(description) = self.description {
+            header.push_str(" - ");
+            header.push_str(description);
+        }
+        let mut lines = vec![truncate(&header, width)];
+        for variant in self.variants {
+            let mut line = format!("  {}", self.name);
+            if line.chars().count() + 1 + variant.usage.chars().count() <= width {
+                if !variant.usage.is_empty() {
+                    line.push(' ');
+                    line.push_str(variant.usage);
+                }
+            } else {
+                // Only wrap lines that already contain a part, so that no line is left empty.
+                let mut has_part = false;
+                for part in variant.syntax.iter().map(SyntaxNode::to_string) {
+                    if has_part && line.chars().count() + 1 + part.chars().count() > width {
+                        lines.push(truncate(&line, width));
+                        line = String::from("     ");
+                    }
+                    line.push(' ');
+                    line.push_str(&part);
+                    has_part = true;
+                }
+            }
+            lines.push(truncate(&line, width));
+            for argument in variant.arguments() {
+                if let Some(argument_help) = argument.help {
+                    let line = format!("    <{}>: {}", argument.name, argument_help);
+                    lines.push(truncate(&line, width));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// The usage and syntax of a single command variant.
+/// Generated automatically.
+#[derive(Clone, Debug)]
+pub struct VariantUsage {
+    pub usage: &'static str,
+    /// A stable key identifying the variant for localization, e.g. `commands.teleport.to_player`.
+    pub key: &'static str,
+    pub syntax: &'static [SyntaxNode],
+    /// Where the variant is defined.
+    pub location: SourceLocation,
+}
+
+/// The place in the source code a command variant is defined at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The path of the module generated for the command by `define_command!`.
+    pub module_path: &'static str,
+    pub file: &'static str,
+    pub line: u32,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+impl VariantUsage {
+    /// Iterate over the arguments of this variant.
+    pub fn arguments(&self) -> impl Iterator<Item = &'static ArgumentUsage> {
+        self.syntax.iter().filter_map(|node| match node {
+            SyntaxNode::Argument(argument) => Some(argument),
+            SyntaxNode::Literal(_) => None,
+        })
+    }
+
+    /// The number of arguments of this variant.
+    pub fn arity(&self) -> usize {
+        self.arguments().count()
+    }
+
+    /// The number of arguments of this variant that have to be given, i.e. excluding optional ones.
+    pub fn required_arity(&self) -> usize {
+        self.arguments()
+            .filter(|argument| !argument.optional)
+            .count()
+    }
+
+    /// The type names of the arguments of this variant, e.g. `["String", "u32"]`.
+    pub fn argument_types(&self) -> Vec<&'static str> {
+        self.arguments().map(|argument| argument.ty).collect()
+    }
+}
+
+/// An element of a command variant's syntax, excluding the command name.
+#[derive(Clone, Debug)]
+pub enum SyntaxNode {
+    Literal(&'static str),
+    Argument(ArgumentUsage),
+}
+
+/// Displays a literal as-is and an argument like `<count: u32>`, or `[<count: u32>]` if it is optional.
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxNode::Literal(literal) => f.write_str(literal),
+            SyntaxNode::Argument(argument) if argument.optional => {
+                write!(f, "[<{}: {}>]", argument.name, argument.ty)
+            }
+            SyntaxNode::Argument(argument) => write!(f, "<{}: {}>", argument.name, argument.ty),
+        }
+    }
+}
+
+/// Helper type used to parse a single argument without producing its value.
+pub type ParseArgument =
+    for<'a> fn(&mut parser::CommandParser<'a>) -> Result<(), parser::ParseError<'a>>;
+
+/// Helper type used to get suggestions for an argument.
+pub type SuggestArgument = for<'a> fn(&parser::CommandParser<'a>) -> Vec<String>;
+
+/// Describes an argument of a command variant.
+#[derive(Clone, Debug)]
+pub struct ArgumentUsage {
+    pub name: &'static str,
+    /// A stable key identifying the argument for localization, e.g. `commands.teleport.to_player.target`.
+    pub key: &'static str,
+    pub ty: &'static str,
+    pub help: Option<&'static str>,
+    /// Whether the argument may be omitted, which is the case for trailing `Option<T>` parameters.
+    pub optional: bool,
+    pub parse: ParseArgument,
+    pub suggest: SuggestArgument,
+}
+
+/// Rewrites the input following a command's name before it is parsed, see `CommandSource::set_preprocessor`.
+pub type Preprocessor = Rc<dyn Fn(&str) -> String>;
+
+/// Decides whether a command may be run in the given context, see `CommandSource::set_permission_check`.
+pub type PermissionCheck<Context> = Rc<dyn Fn(&Context, &CommandUsage) -> bool>;
+
+/// Provides the context to commands, see `CommandSource::with_context_accessor`.
+type ContextAccessor<Context> = Rc<dyn Fn() -> Option<Rc<dyn ContextCell<Context>>>>;
+
+/// Borrows the context of a `CommandSource`, which is either held in a `RefCell`
+/// or projected from the context of another source, see `CommandSource::adapt`.
+/// Borrowing returns `None` if the context is already borrowed in a conflicting way.
+trait ContextCell<Context> {
+    fn try_borrow_context(&self) -> Option<Ref<'_, Context>>;
+    fn try_borrow_context_mut(&self) -> Option<RefMut<'_, Context>>;
+}
+
+impl<Context> ContextCell<Context> for RefCell<Context> {
+    fn try_borrow_context(&self) -> Option<Ref<'_, Context>> {
+        self.try_borrow().ok()
+    }
+
+    fn try_borrow_context_mut(&self) -> Option<RefMut<'_, Context>> {
+        self.try_borrow_mut().ok()
+    }
+}
+
+/// The part of a context that the commands of a source created by `CommandSource::adapt` run against.
+struct Projection<Parent, Sub> {
+    parent: Rc<dyn ContextCell<Parent>>,
+    get: Rc<dyn Fn(&Parent) -> &Sub>,
+    get_mut: Rc<dyn Fn(&mut Parent) -> &mut Sub>,
+}
+
+impl<Parent, Sub> ContextCell<Sub> for Projection<Parent, Sub> {
+    fn try_borrow_context(&self) -> Option<Ref<'_, Sub>> {
+        let parent = self.parent.try_borrow_context()?;
+        Some(Ref::map(parent, |parent| (self.get)(parent)))
+    }
+
+    fn try_borrow_context_mut(&self) -> Option<RefMut<'_, Sub>> {
+        let parent = self.parent.try_borrow_context_mut()?;
+        Some(RefMut::map(parent, |parent| (self.get_mut)(parent)))
+    }
+}
+
+/// Handles commands with unknown names, see `CommandSource::set_fallback`.
+pub type Fallback<Context> = Rc<dyn Fn(&Context, &str) -> CommandResult<'static>>;
+
+/// Observes every completed dispatch, see `CommandSource::set_dispatch_hook`.
+pub type DispatchHook = Rc<dyn Fn(&dispatch::DispatchEvent)>;
+
+/// Observes changes to the registered commands, see `CommandSource::on_change`.
+pub type ChangeListener = Rc<dyn Fn(&schema::SchemaDiff)>;
+
 struct Command<Context: 'static> {
     usage: &'static CommandUsage,
     dispatchers: &'static [CommandDispatch<Context>],
+    preprocessor: Option<Preprocessor>,
+    location: &'static Location<'static>,
+    /// Disabled commands are treated as if they weren't registered, see `CommandSource::set_enabled`.
+    enabled: bool,
+    /// Whether the command's input is never searched for flags, see `CommandSource::set_flagless`.
+    flagless: bool,
+}
+
+// Implemented manually, as deriving would require `Context: Clone`
+impl<Context> Clone for Command<Context> {
+    fn clone(&self) -> Self {
+        Self {
+            usage: self.usage,
+            dispatchers: self.dispatchers,
+            preprocessor: self.preprocessor.clone(),
+            location: self.location,
+            enabled: self.enabled,
+            flagless: self.flagless,
+        }
+    }
 }
 
+/// A command whose syntax is only known at runtime, see `CommandSource::register_dynamic`.
+struct DynamicCommand<Context: 'static> {
+    syntax: Rc<dynamic::DynamicSyntax>,
+    execute: DynamicExecute<Context>,
+}
+
+// Implemented manually, as deriving would require `Context: Clone`
+impl<Context> Clone for DynamicCommand<Context> {
+    fn clone(&self) -> Self {
+        Self {
+            syntax: self.syntax.clone(),
+            execute: self.execute.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Alias {
+    target: &'static str,
+    location: &'static Location<'static>,
+}
+
+/// Commands implemented by `oberst` itself, which can be enabled using `CommandSource::enable_builtin`.
+/// Commands registered under the same name take precedence over built-ins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Builtin {
+    /// `which <command>`: writes where a command is defined and registered, along with its aliases, to the output.
+    Which,
+    /// `history`: writes the numbered history of dispatched commands to the output, see `CommandSource::history`.
+    History,
+}
+
+impl Builtin {
+    /// The name the built-in is dispatched with.
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Which => "which",
+            Builtin::History => "history",
+        }
+    }
+}
+
+/// Produces the values of a literal set from the context, see `CommandSource::register_literals`.
+pub type LiteralProvider<Context> = Rc<dyn Fn(&Context) -> Vec<String>>;
+
+/// Receives text written by built-in commands and `write_output`, see `CommandSource::set_output`.
+pub type Output = Rc<dyn Fn(&str)>;
+
+thread_local! {
+    /// The outputs of the sources executing commands on this thread, innermost last.
+    static OUTPUTS: RefCell<Vec<Output>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Write text to the output of the source executing the current command, see `CommandSource::set_output`.
+/// This is how commands produce output that can be piped into the next command of a chain.
+/// Outside of a command, the text is printed to standard output.
+pub fn write_output(text: &str) {
+    match OUTPUTS.with(|outputs| outputs.borrow().last().cloned()) {
+        Some(output) => output(text),
+        None => println!("{}", text),
+    }
+}
+
+/// Makes an output the target of `write_output` until dropped.
+struct OutputScope;
+
+impl OutputScope {
+    fn enter(output: Output) -> Self {
+        OUTPUTS.with(|outputs| outputs.borrow_mut().push(output));
+        OutputScope
+    }
+}
+
+impl Drop for OutputScope {
+    fn drop(&mut self) {
+        OUTPUTS.with(|outputs| outputs.borrow_mut().pop());
+    }
+}
+
+/// Restores the output of a source replaced by `CommandSource::capture_output` when dropped.
+struct RestoreOutput<'s> {
+    output: &'s RefCell<Option<Output>>,
+    previous: Option<Output>,
+}
+
+impl Drop for RestoreOutput<'_> {
+    fn drop(&mut self) {
+        *self.output.borrow_mut() = self.previous.take();
+    }
+}
+
+/// What a name is registered as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registration {
+    Command,
+    Alias { target: &'static str },
+}
+
+/// Describes a name that was registered more than once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The name that was registered more than once.
+    pub name: &'static str,
+    /// What the name was previously registered as.
+    pub existing: Registration,
+    /// Where the name was previously registered.
+    pub existing_location: &'static Location<'static>,
+    /// Where the conflicting registration happened.
+    pub location: &'static Location<'static>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` registered at {} is already ",
+            self.name, self.location
+        )?;
+        match self.existing {
+            Registration::Command => write!(f, "a command")?,
+            Registration::Alias { target } => write!(f, "an alias for `{}`", target)?,
+        }
+        write!(f, " registered at {}", self.existing_location)
+    }
+}
+
+/// An error that occurs when registering a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistrationError {
+    /// The name is already taken by another command or alias.
+    Conflict(Conflict),
+    /// The command has no variants to dispatch to.
+    NoDispatchers,
+}
+
+impl Display for RegistrationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistrationError::Conflict(conflict) => conflict.fmt(f),
+            RegistrationError::NoDispatchers => write!(f, "The command has no variants"),
+        }
+    }
+}
+
+impl std::error::Error for RegistrationError {}
+
 /// Helper struct generated by `define_command!`
 pub struct CommandDispatch<Context> {
     pub parser: Parse<Context>,
 }
 
+/// A command along with one dispatcher for each of its `N` variants, see `CommandSource::register_spec`.
+/// `define_command!` generates one named `SPEC` for each command.
+pub struct CommandSpec<Context: 'static, const N: usize> {
+    pub name: &'static str,
+    pub usage: &'static CommandUsage,
+    pub dispatchers: &'static [CommandDispatch<Context>; N],
+}
+
+impl<Context: 'static, const N: usize> CommandSpec<Context, N> {
+    /// Describe a command whose usage lists exactly `N` variants, one for each dispatcher.
+    /// Creating the spec in a `static` or `const` checks this at compile time, otherwise it panics on a mismatch.
+    pub const fn new(
+        name: &'static str,
+        usage: &'static CommandUsage,
+        dispatchers: &'static [CommandDispatch<Context>; N],
+    ) -> Self {
+        assert!(
+            usage.variants.len() == N,
+            "the number of variants in the usage doesn't match the number of dispatchers"
+        );
+        Self {
+            name,
+            usage,
+            dispatchers,
+        }
+    }
+}
+
 /// The core of `oberst`. This struct manages commands and allows them to be dispatched.
-#[derive(Clone)]
+/// Clones share their commands and context, so commands registered through one clone are available to all of them.
+/// Use `overlay` for a copy with its own set of commands instead.
 pub struct CommandSource<Context: 'static> {
     commands: Rc<RefCell<HashMap<&'static str, Command<Context>>>>,
-    context: Rc<Context>,
+    aliases: Rc<RefCell<HashMap<&'static str, Alias>>>,
+    dynamic_commands: Rc<RefCell<HashMap<String, DynamicCommand<Context>>>>,
+    builtins: Rc<RefCell<Vec<Builtin>>>,
+    output: Rc<RefCell<Option<Output>>>,
+    literals: Rc<RefCell<HashMap<String, LiteralProvider<Context>>>>,
+    permission_check: Rc<RefCell<Option<PermissionCheck<Context>>>>,
+    fallback: Rc<RefCell<Option<Fallback<Context>>>>,
+    dispatch_hook: Rc<RefCell<Option<DispatchHook>>>,
+    change_listeners: Rc<RefCell<Vec<ChangeListener>>>,
+    suggestion_cache: Rc<RefCell<Option<completion::SuggestionCache>>>,
+    usage: Rc<RefCell<completion::UsageStats>>,
+    history: Rc<RefCell<history::History>>,
+    #[cfg(feature = "async")]
+    in_flight: Rc<shutdown::InFlight>,
+    arguments: Rc<RefCell<dynamic::ArgumentRegistry>>,
+    config: Rc<parser::ParserConfig>,
+    context: ContextAccessor<Context>,
+}
+
+// Implemented manually, as deriving would require `Context: Clone`
+impl<Context: 'static> Clone for CommandSource<Context> {
+    fn clone(&self) -> Self {
+        Self {
+            commands: self.commands.clone(),
+            aliases: self.aliases.clone(),
+            dynamic_commands: self.dynamic_commands.clone(),
+            builtins: self.builtins.clone(),
+            output: self.output.clone(),
+            literals: self.literals.clone(),
+            permission_check: self.permission_check.clone(),
+            fallback: self.fallback.clone(),
+            dispatch_hook: self.dispatch_hook.clone(),
+            change_listeners: self.change_listeners.clone(),
+            suggestion_cache: self.suggestion_cache.clone(),
+            usage: self.usage.clone(),
+            history: self.history.clone(),
+            #[cfg(feature = "async")]
+            in_flight: self.in_flight.clone(),
+            arguments: self.arguments.clone(),
+            config: self.config.clone(),
+            context: self.context.clone(),
+        }
+    }
 }
 
 impl<Context: 'static> CommandSource<Context> {
     /// Create a new `CommandSource` with the given context.
     /// The context will be passed to all commands.
     pub fn new(context: Context) -> Self {
+        Self::with_config(context, Default::default())
+    }
+
+    /// Create a new `CommandSource` with the given context, parsing commands using the given configuration.
+    pub fn with_config(context: Context, config: parser::ParserConfig) -> Self {
+        let context = Rc::new(RefCell::new(context));
+        Self::with_context_accessor(move || Some(context.clone()), config)
+    }
+
+    /// Create a new `CommandSource` whose context is owned by the host and retrieved using `accessor` for each command,
+    /// e.g. `move || weak.upgrade()` for a context held in a `Weak`.
+    /// Once the accessor returns `None`, e.g. because a plugin holding the context was unloaded,
+    /// commands fail with `CommandError::ContextUnavailable`.
+    pub fn with_context_accessor(
+        accessor: impl Fn() -> Option<Rc<RefCell<Context>>> + 'static,
+        config: parser::ParserConfig,
+    ) -> Self {
+        Self::from_accessor(
+            Rc::new(move || accessor().map(|context| context as Rc<dyn ContextCell<Context>>)),
+            config,
+        )
+    }
+
+    fn from_accessor(context: ContextAccessor<Context>, config: parser::ParserConfig) -> Self {
         Self {
             commands: Default::default(),
-            context: Rc::new(context),
+            aliases: Default::default(),
+            dynamic_commands: Default::default(),
+            builtins: Default::default(),
+            output: Default::default(),
+            literals: Default::default(),
+            permission_check: Default::default(),
+            fallback: Default::default(),
+            dispatch_hook: Default::default(),
+            change_listeners: Default::default(),
+            suggestion_cache: Default::default(),
+            usage: Default::default(),
+            history: Default::default(),
+            #[cfg(feature = "async")]
+            in_flight: Default::default(),
+            arguments: Rc::new(RefCell::new(dynamic::ArgumentRegistry::with_builtins())),
+            config: Rc::new(config),
+            context,
         }
     }
 
+    /// Create a source sharing the context and configuration of this one, but with a copy of its commands and aliases.
+    /// Commands registered to the overlay, possibly replacing existing ones, are not visible to this source,
+    /// so a temporary scope such as a minigame can drop its overlay to return to the original set of commands.
+    /// Listeners added using `on_change` are not notified of changes to the overlay.
+    pub fn overlay(&self) -> Self {
+        let suggestion_cache = self
+            .suggestion_cache
+            .borrow()
+            .as_ref()
+            .map(|cache| completion::SuggestionCache::new(cache.capacity()));
+        Self {
+            commands: Rc::new(RefCell::new(self.commands.borrow().clone())),
+            aliases: Rc::new(RefCell::new(self.aliases.borrow().clone())),
+            dynamic_commands: Rc::new(RefCell::new(self.dynamic_commands.borrow().clone())),
+            suggestion_cache: Rc::new(RefCell::new(suggestion_cache)),
+            change_listeners: Default::default(),
+            ..self.clone()
+        }
+    }
+
+    /// Create a source for commands written against a narrower context `Sub`, e.g. those of a reusable command library,
+    /// which `get` and `get_mut` project from this source's context for each dispatch,
+    /// e.g. `source.adapt(|app: &App| &app.world, |app: &mut App| &mut app.world)`.
+    /// The new source starts without commands and shares the configuration and output of this one.
+    /// Its commands borrow this source's context, so they fail with `CommandError::ContextBorrowed` while it is in use
+    /// by a conflicting command, and with `CommandError::ContextUnavailable` once it is no longer available.
+    pub fn adapt<Sub: 'static>(
+        &self,
+        get: impl Fn(&Context) -> &Sub + 'static,
+        get_mut: impl Fn(&mut Context) -> &mut Sub + 'static,
+    ) -> CommandSource<Sub> {
+        let context = self.context.clone();
+        let get: Rc<dyn Fn(&Context) -> &Sub> = Rc::new(get);
+        let get_mut: Rc<dyn Fn(&mut Context) -> &mut Sub> = Rc::new(get_mut);
+        let mut source = CommandSource::from_accessor(
+            Rc::new(move || {
+                let projection = Projection {
+                    parent: context()?,
+                    get: get.clone(),
+                    get_mut: get_mut.clone(),
+                };
+                Some(Rc::new(projection) as Rc<dyn ContextCell<Sub>>)
+            }),
+            Default::default(),
+        );
+        source.config = self.config.clone();
+        source.output = self.output.clone();
+        source
+    }
+
+    /// Create a builder for a `CommandSource` with the given context, collecting its configuration in one place.
+    pub fn builder(context: Context) -> builder::CommandSourceBuilder<Context> {
+        builder::CommandSourceBuilder::new(context)
+    }
+
+    /// The configuration used to parse commands.
+    pub fn config(&self) -> &parser::ParserConfig {
+        &self.config
+    }
+
     /// Register a command with the given name, usage and dispatchers.
-    /// Use the `register_command!` macro instead of calling this method directly.
+    /// Use the `register_command!` macro or `register_spec` instead of calling this method directly.
+    /// A command previously registered under the same name is replaced, in which case the conflict is returned.
+    /// Commands without dispatchers fail with `CommandError::NoVariants` when dispatched.
+    #[track_caller]
     pub fn register(
         &self,
         name: &'static str,
         usage: &'static CommandUsage,
         dispatchers: &'static [CommandDispatch<Context>],
-    ) {
-        assert!(!dispatchers.is_empty());
-        debug_assert!(name.chars().all(char::is_alphabetic));
+    ) -> Option<Conflict> {
+        debug_assert!(!name.is_empty() && !name.contains(char::is_whitespace));
+        let location = Location::caller();
+        let conflict = self.conflict(name, location);
+        self.change(|| {
+            self.commands.borrow_mut().insert(
+                name,
+                Command {
+                    usage,
+                    dispatchers,
+                    preprocessor: None,
+                    location,
+                    enabled: true,
+                    flagless: false,
+                },
+            );
+        });
+        conflict
+    }
+
+    /// Register a command like `register`, taking its name, usage and dispatchers from a spec
+    /// whose variant count has been checked against its dispatchers.
+    #[track_caller]
+    pub fn register_spec<const N: usize>(
+        &self,
+        spec: &CommandSpec<Context, N>,
+    ) -> Option<Conflict> {
+        self.register(spec.name, spec.usage, spec.dispatchers)
+    }
+
+    /// Remove the command with the given name along with its aliases.
+    /// Returns `false` if there is no command with the given name.
+    pub fn unregister(&self, name: &str) -> bool {
+        if !self.commands.borrow().contains_key(name) {
+            return false;
+        }
+        self.change(|| {
+            self.commands.borrow_mut().remove(name);
+            self.aliases
+                .borrow_mut()
+                .retain(|_, alias| alias.target != name);
+        });
+        true
+    }
+
+    /// Enable or disable the given command or alias. Commands are enabled when they are registered.
+    /// Disabled commands are treated as if they weren't registered, but keep their aliases and preprocessor.
+    /// Returns `false` if there is no command with the given name.
+    pub fn set_enabled(&self, command: &str, enabled: bool) -> bool {
+        let command = self.resolve(command);
+        if !self.commands.borrow().contains_key(command) {
+            return false;
+        }
+        self.change(|| {
+            if let Some(command) = self.commands.borrow_mut().get_mut(command) {
+                command.enabled = enabled;
+            }
+        });
+        true
+    }
+
+    /// Whether the given command or alias is registered and enabled, see `set_enabled`.
+    pub fn is_enabled(&self, command: &str) -> bool {
+        self.enabled_command(command).is_some()
+    }
+
+    /// Get the given command or alias unless it is disabled.
+    fn enabled_command(&self, command: &str) -> Option<Command<Context>> {
         self.commands
+            .borrow()
+            .get(self.resolve(command))
+            .filter(|command| command.enabled)
+            .cloned()
+    }
+
+    /// Add a listener which is notified whenever commands are registered, unregistered, enabled or disabled,
+    /// or aliases are added, with the difference between the previous and the new `schema`.
+    /// This allows connected clients to update their copy of the command tree instead of polling it.
+    pub fn on_change(&self, listener: impl Fn(&schema::SchemaDiff) + 'static) {
+        self.change_listeners.borrow_mut().push(Rc::new(listener));
+    }
+
+    /// Apply a change to the registered commands, clearing the suggestion cache and notifying listeners of the difference.
+    fn change(&self, change: impl FnOnce()) {
+        self.clear_suggestion_cache();
+        let listeners = self.change_listeners.borrow().clone();
+        if listeners.is_empty() {
+            change();
+            return;
+        }
+        let previous = self.schema();
+        change();
+        let diff = self.schema().diff(&previous);
+        if !diff.is_empty() {
+            for listener in listeners {
+                listener(&diff);
+            }
+        }
+    }
+
+    /// Register a command like `register`, unless its name is already taken by another command or alias
+    /// or it has no dispatchers.
+    #[track_caller]
+    pub fn try_register(
+        &self,
+        name: &'static str,
+        usage: &'static CommandUsage,
+        dispatchers: &'static [CommandDispatch<Context>],
+    ) -> Result<(), RegistrationError> {
+        if dispatchers.is_empty() {
+            return Err(RegistrationError::NoDispatchers);
+        }
+        if let Some(conflict) = self.conflict(name, Location::caller()) {
+            return Err(RegistrationError::Conflict(conflict));
+        }
+        self.register(name, usage, dispatchers);
+        Ok(())
+    }
+
+    /// Get the existing registration of `name` as a conflict with a registration at `location`.
+    fn conflict(
+        &self,
+        name: &'static str,
+        location: &'static Location<'static>,
+    ) -> Option<Conflict> {
+        let (existing, existing_location) = match self.commands.borrow().get(name) {
+            Some(command) => (Registration::Command, command.location),
+            None => {
+                let aliases = self.aliases.borrow();
+                let alias = aliases.get(name)?;
+                (
+                    Registration::Alias {
+                        target: alias.target,
+                    },
+                    alias.location,
+                )
+            }
+        };
+        Some(Conflict {
+            name,
+            existing,
+            existing_location,
+            location,
+        })
+    }
+
+    /// Set a function that rewrites the input following the name of the given command before it is parsed,
+    /// e.g. to strip color codes or normalize full-width characters.
+    /// Errors of commands whose input was changed no longer borrow it, see `CommandError::into_owned`.
+    /// Returns `false` if there is no command with the given name.
+    pub fn set_preprocessor(
+        &self,
+        command: &str,
+        preprocessor: impl Fn(&str) -> String + 'static,
+    ) -> bool {
+        let command = self.resolve(command);
+        if !self.commands.borrow().contains_key(command) {
+            return false;
+        }
+        self.change(|| {
+            if let Some(command) = self.commands.borrow_mut().get_mut(command) {
+                command.preprocessor = Some(Rc::new(preprocessor));
+            }
+        });
+        true
+    }
+
+    /// Set whether the input of the given command or alias is never searched for flags, i.e. the help suffix
+    /// and engine flags, see `ParserConfig::help_suffix` and `ParserConfig::engine_flags`,
+    /// e.g. for `say`, where a trailing `?` or `--silent` is part of the message.
+    /// This applies to all of the command's aliases.
+    /// Returns `false` if there is no command with the given name.
+    pub fn set_flagless(&self, command: &str, flagless: bool) -> bool {
+        let command = self.resolve(command);
+        if !self.commands.borrow().contains_key(command) {
+            return false;
+        }
+        self.change(|| {
+            if let Some(command) = self.commands.borrow_mut().get_mut(command) {
+                command.flagless = flagless;
+            }
+        });
+        true
+    }
+
+    /// Register a command whose syntax is only known at runtime, e.g. one loaded from a script,
+    /// which runs `execute` with the arguments parsed by `syntax`. Its argument kinds are resolved through `arguments`,
+    /// which also provides their suggestions.
+    /// Commands and aliases registered under the same name take precedence,
+    /// and dynamic commands are neither subject to the permission check nor included in the `schema`.
+    /// A dynamic command previously registered under the same name is replaced.
+    pub fn register_dynamic(
+        &self,
+        name: impl Into<String>,
+        syntax: dynamic::DynamicSyntax,
+        execute: impl Fn(&Context, dynamic::DynamicArguments) -> CommandResult<'static> + 'static,
+    ) {
+        let name = name.into();
+        debug_assert!(!name.is_empty() && !name.contains(char::is_whitespace));
+        self.change(|| {
+            self.dynamic_commands.borrow_mut().insert(
+                name,
+                DynamicCommand {
+                    syntax: Rc::new(syntax),
+                    execute: Rc::new(execute),
+                },
+            );
+        });
+    }
+
+    /// Get the dynamic command with the given name unless an enabled command or alias is registered under it.
+    fn dynamic_command(&self, name: &str) -> Option<DynamicCommand<Context>> {
+        if self.enabled_command(name).is_some() {
+            return None;
+        }
+        self.dynamic_commands.borrow().get(name).cloned()
+    }
+
+    /// Register `alias` as another name for the command `target`, which may itself be an alias.
+    /// Commands registered under the same name take precedence over the alias.
+    /// Returns `false` if there is no command with the given name.
+    #[track_caller]
+    pub fn register_alias(&self, alias: &'static str, target: &str) -> bool {
+        debug_assert!(!alias.is_empty() && !alias.contains(char::is_whitespace));
+        let target = self.resolve(target);
+        let Some((&target, _)) = self.commands.borrow().get_key_value(target) else {
+            return false;
+        };
+        let location = Location::caller();
+        self.change(|| {
+            self.aliases
+                .borrow_mut()
+                .insert(alias, Alias { target, location });
+        });
+        true
+    }
+
+    /// Get the aliases of the given command in alphabetical order.
+    pub fn aliases(&self, command: &str) -> Vec<&'static str> {
+        let command = self.resolve(command);
+        let mut aliases = self
+            .aliases
+            .borrow()
+            .iter()
+            .filter(|(_, alias)| alias.target == command)
+            .map(|(alias, _)| *alias)
+            .collect::<Vec<_>>();
+        aliases.sort();
+        aliases
+    }
+
+    /// Register a set of literals whose values are produced from the context whenever a command is parsed,
+    /// e.g. the names of the currently loaded worlds.
+    /// Parameters annotated with `#[literals = "..."]` only accept values of the set with the given name,
+    /// which are also offered as suggestions.
+    pub fn register_literals(
+        &self,
+        set: impl Into<String>,
+        provider: impl Fn(&Context) -> Vec<String> + 'static,
+    ) {
+        self.clear_suggestion_cache();
+        self.literals
             .borrow_mut()
-            .insert(name, Command { usage, dispatchers });
+            .insert(set.into(), Rc::new(provider));
+    }
+
+    /// Create a parser for the given command, which can look up the literal sets of this source.
+    fn parser<'a>(&self, command: &'a str) -> parser::CommandParser<'a> {
+        let context = self.context.clone();
+        let providers = self.literals.clone();
+        parser::CommandParser::with_config(command, self.config.clone()).with_literals(Rc::new(
+            move |set| {
+                let provider = providers.borrow().get(set)?.clone();
+                let handle = context()?;
+                let context = handle.try_borrow_context()?;
+                Some(provider(&context))
+            },
+        ))
     }
 
-    /// Get the usage information for the given command.
+    /// Enable a command implemented by `oberst` itself.
+    pub fn enable_builtin(&self, builtin: Builtin) {
+        let mut builtins = self.builtins.borrow_mut();
+        if !builtins.contains(&builtin) {
+            self.clear_suggestion_cache();
+            builtins.push(builtin);
+        }
+    }
+
+    /// Set a check that runs before each command with the context and the command's usage.
+    /// Commands for which it returns `false` fail with `CommandError::PermissionDenied` instead of running.
+    pub fn set_permission_check(&self, check: impl Fn(&Context, &CommandUsage) -> bool + 'static) {
+        *self.permission_check.borrow_mut() = Some(Rc::new(check));
+    }
+
+    /// Set a handler for commands whose name matches neither a command, an alias, a dynamic command nor an enabled built-in.
+    /// It receives the context and the whole command, e.g. to forward it to a script engine.
+    /// Without a fallback, such commands fail with `ParseErrorKind::UnknownCommand`.
+    pub fn set_fallback(
+        &self,
+        fallback: impl Fn(&Context, &str) -> CommandResult<'static> + 'static,
+    ) {
+        *self.fallback.borrow_mut() = Some(Rc::new(fallback));
+    }
+
+    /// Set a hook that is called after each dispatch with the command, its correlation id and its result,
+    /// e.g. to write an audit log.
+    pub fn set_dispatch_hook(&self, hook: impl Fn(&dispatch::DispatchEvent) + 'static) {
+        *self.dispatch_hook.borrow_mut() = Some(Rc::new(hook));
+    }
+
+    /// Pass a completed dispatch to the dispatch hook.
+    fn notify(&self, command: &str, options: &dispatch::DispatchOptions, result: &CommandResult) {
+        if result.is_ok() {
+            self.count_usage(command);
+        }
+        #[cfg(feature = "tracing")]
+        match result {
+            Ok(status) => tracing::debug!(status, "command completed"),
+            Err(error) => tracing::debug!(%error, "command failed"),
+        }
+        let hook = self.dispatch_hook.borrow().clone();
+        if let Some(hook) = hook {
+            hook(&dispatch::DispatchEvent {
+                command,
+                correlation_id: options.correlation_id.as_deref(),
+                flags: &options.flags,
+                result: result.as_ref().copied(),
+            });
+        }
+    }
+
+    /// Record a successful dispatch of the command in `command`, see `usage_count` and `recent_commands`.
+    fn count_usage(&self, command: &str) {
+        let mut parser = self.parser(command);
+        let name = (self.config.command_name)(&mut parser);
+        let commands = self.commands.borrow();
+        let Some((name, _)) = commands.get_key_value(self.resolve(name)) else {
+            return;
+        };
+        self.usage.borrow_mut().record(name);
+        if self.config.suggestion_ranking == completion::SuggestionRanking::Frequency {
+            self.clear_suggestion_cache();
+        } else if let Some(cache) = self.suggestion_cache.borrow_mut().as_mut() {
+            // Suggestions for an empty prompt depend on the recently used commands
+            cache.remove("");
+        }
+    }
+
+    /// How often the given command or alias was dispatched successfully by this source or its clones.
+    /// Used to rank suggestions by `SuggestionRanking::Frequency`.
+    pub fn usage_count(&self, command: &str) -> u64 {
+        self.usage.borrow().count(self.resolve(command))
+    }
+
+    /// The commands most recently dispatched successfully by this source or its clones, most recent first.
+    /// Suggested first when completing an empty prompt.
+    pub fn recent_commands(&self) -> Vec<&'static str> {
+        self.usage.borrow().recent().collect()
+    }
+
+    /// The commands dispatched by this source or its clones along with their numbers, oldest first.
+    /// Commands are remembered as they were run, i.e. after expanding references like `!!`,
+    /// up to `ParserConfig::history_size` of them.
+    pub fn history(&self) -> Vec<(usize, String)> {
+        self.history
+            .borrow()
+            .entries()
+            .map(|(number, command)| (number, command.to_string()))
+            .collect()
+    }
+
+    /// Expand references to previous commands if `ParserConfig::history_expansion` is enabled,
+    /// then add the command to the history.
+    fn expand_history<'a>(&self, command: &'a str) -> Result<Cow<'a, str>, parser::ParseError<'a>> {
+        let expanded = if self.config.history_expansion {
+            self.history
+                .borrow()
+                .expand(command)
+                .map_err(|(offset, reference)| {
+                    let mut parser = self.parser(command);
+                    parser.advance(offset);
+                    parser.custom_error(format!("no command `{}` in the history", reference))
+                })?
+        } else {
+            Cow::Borrowed(command)
+        };
+        self.history
+            .borrow_mut()
+            .record(&expanded, self.config.history_size);
+        Ok(expanded)
+    }
+
+    /// The span dispatches are traced in when the `tracing` feature is enabled.
+    #[cfg(feature = "tracing")]
+    fn span(&self, command: &str, options: &dispatch::DispatchOptions) -> tracing::Span {
+        tracing::info_span!(
+            "dispatch",
+            command,
+            correlation_id = options.correlation_id.as_deref()
+        )
+    }
+
+    /// Set where text written by built-in commands and by commands using `write_output` goes. Defaults to standard output.
+    pub fn set_output(&self, output: impl Fn(&str) + 'static) {
+        *self.output.borrow_mut() = Some(Rc::new(output));
+    }
+
+    fn output(&self) -> Output {
+        self.output
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| Rc::new(|text| println!("{}", text)))
+    }
+
+    /// Describe where the given command or alias is defined and registered, along with its aliases.
+    pub fn which(&self, command: &str) -> Option<String> {
+        let name = self.resolve(command);
+        let commands = self.commands.borrow();
+        let (&name, command) = commands.get_key_value(name)?;
+        let mut report = name.to_string();
+        let aliases = self.aliases(name);
+        if !aliases.is_empty() {
+            report.push_str(&format!(" (aliases: {})", aliases.join(", ")));
+        }
+        report.push_str(&format!("\n  registered at {}", command.location));
+        for variant in command.usage.variants {
+            report.push_str(&format!("\n  {}", name));
+            if !variant.usage.is_empty() {
+                report.push(' ');
+                report.push_str(variant.usage);
+            }
+            report.push_str(&format!(
+                " defined in {} at {}",
+                variant.location.module_path, variant.location
+            ));
+        }
+        Some(report)
+    }
+
+    /// Parse the arguments of a built-in command.
+    fn parse_builtin<'a>(
+        &self,
+        builtin: Builtin,
+        parser: &mut parser::CommandParser<'a>,
+    ) -> Result<Execute<'a, Context>, parser::ParseError<'a>> {
+        let output = self.output();
+        match builtin {
+            Builtin::Which => {
+                parser.spacing()?;
+                let start = parser.branch();
+                let command = (self.config.command_name)(parser);
+                parser.end()?;
+                let report = self
+                    .which(command)
+                    .ok_or_else(|| start.error(parser::ParseErrorKind::UnknownCommand))?;
+                Ok(Execute::Sync(Box::new(move |_| {
+                    output(&report);
+                    Ok(0)
+                })))
+            }
+            Builtin::History => {
+                parser.end()?;
+                let report = self
+                    .history()
+                    .into_iter()
+                    .map(|(number, command)| format!("{:>5}  {}", number, command))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Ok(Execute::Sync(Box::new(move |_| {
+                    output(&report);
+                    Ok(0)
+                })))
+            }
+        }
+    }
+
+    /// Get the name of the command `name` refers to, resolving aliases.
+    fn resolve<'n>(&self, name: &'n str) -> &'n str {
+        if self.commands.borrow().contains_key(name) {
+            return name;
+        }
+        self.aliases
+            .borrow()
+            .get(name)
+            .map_or(name, |alias| alias.target)
+    }
+
+    /// Get the usage information for the given command or alias.
     pub fn get_usage(&self, command: &str) -> Option<&'static CommandUsage> {
         self.commands
             .borrow()
-            .get(command)
+            .get(self.resolve(command))
             .map(|command| command.usage)
     }
 
-    /// Dispatch a command described by the string in `command`.
-    pub fn dispatch<'a>(&'a self, command: &'a str) -> CommandResult {
-        let mut parser = parser::CommandParser::new(command);
-        let command = parser.read_while(|c| c.is_alphabetic());
-        let map = self.commands.borrow();
-        let command = map.get(&command).ok_or(CommandError::Parse(
-            parser.error(parser::ParseErrorKind::UnknownCommand),
-        ))?;
+    /// Get the variants of the given command or alias, to inspect a command without parsing it.
+    pub fn variants(&self, command: &str) -> Option<&'static [VariantUsage]> {
+        self.get_usage(command).map(|usage| usage.variants)
+    }
+
+    /// Take a snapshot of the enabled commands and their aliases, e.g. to send it to networked clients.
+    /// The types of arguments are resolved to the kinds registered in `arguments`.
+    pub fn schema(&self) -> schema::Schema {
+        let commands = self.commands.borrow();
+        let registry = self.arguments.borrow();
+        schema::Schema::new(commands.iter().filter(|(_, command)| command.enabled).map(
+            |(name, command)| {
+                schema::CommandSchema::new(command.usage, &self.aliases(name), &registry)
+            },
+        ))
+    }
+
+    /// Render a help text listing all commands in alphabetical order, with their aliases shown below them.
+    pub fn help(&self) -> String {
+        self.render_help(usize::MAX)
+    }
+
+    /// Render the help text like `help`, fitting each line into `width` characters, see `CommandUsage::render`.
+    pub fn render_help(&self, width: usize) -> String {
+        let commands = self.commands.borrow();
+        let mut names = commands
+            .iter()
+            .filter(|(_, command)| command.enabled)
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                let mut help = commands[name].usage.render(width);
+                let aliases = self.aliases(name);
+                if !aliases.is_empty() {
+                    help.push('\n');
+                    help.push_str(&truncate(
+                        &format!("  aliases: {}", aliases.join(", ")),
+                        width,
+                    ));
+                }
+                help
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Get the registry of argument kinds known to this source.
+    pub fn arguments(&self) -> Ref<'_, dynamic::ArgumentRegistry> {
+        self.arguments.borrow()
+    }
+
+    /// Get mutable access to the registry of argument kinds known to this source, e.g. to register custom kinds.
+    /// Suggestion providers set with `ArgumentRegistry::register_suggestions` are used to complete all arguments
+    /// whose type resolves to their kind, see `ArgumentRegistry::kind_of`.
+    /// This clears the suggestion cache.
+    pub fn arguments_mut(&self) -> RefMut<'_, dynamic::ArgumentRegistry> {
+        self.clear_suggestion_cache();
+        self.arguments.borrow_mut()
+    }
+
+    /// Get possible completions for the partially typed command in `partial`.
+    /// This includes command names, literals and hints for arguments.
+    pub fn complete(&self, partial: &str) -> Vec<completion::Suggestion> {
+        self.complete_with_stats(partial).0
+    }
+
+    /// Get possible completions like `complete`, along with how long it took to produce them.
+    pub fn complete_with_stats(
+        &self,
+        partial: &str,
+    ) -> (Vec<completion::Suggestion>, completion::CompletionStats) {
+        let start = Instant::now();
+        let cached = self
+            .suggestion_cache
+            .borrow()
+            .as_ref()
+            .and_then(|cache| cache.get(partial));
+        let (suggestions, cached) = match cached {
+            Some(suggestions) => (suggestions, true),
+            None => {
+                let mut suggestions = self.suggest(partial);
+                if let Some(limit) = self.config.suggestion_limit {
+                    suggestions.truncate(limit);
+                }
+                if let Some(cache) = self.suggestion_cache.borrow_mut().as_mut() {
+                    cache.insert(partial, suggestions.clone());
+                }
+                (suggestions, false)
+            }
+        };
+        let stats = completion::CompletionStats {
+            duration: start.elapsed(),
+            cached,
+        };
+        (suggestions, stats)
+    }
+
+    /// Remember the suggestions for up to `capacity` inputs, so that completing them again is instant.
+    /// The cache is cleared whenever commands, aliases, literal sets or builtins are registered,
+    /// or the argument registry is accessed through `arguments_mut`.
+    /// Suggestions depending on the context, such as the values of literal sets, are cached as well,
+    /// so call `clear_suggestion_cache` whenever they change.
+    pub fn enable_suggestion_cache(&self, capacity: usize) {
+        *self.suggestion_cache.borrow_mut() = Some(completion::SuggestionCache::new(capacity));
+    }
+
+    /// Forget all cached suggestions, see `enable_suggestion_cache`.
+    pub fn clear_suggestion_cache(&self) {
+        if let Some(cache) = self.suggestion_cache.borrow_mut().as_mut() {
+            cache.clear();
+        }
+    }
+
+    fn suggest(&self, partial: &str) -> Vec<completion::Suggestion> {
+        let mut parser = self.parser(partial);
+        let name = (self.config.command_name)(&mut parser);
+        let commands = self.commands.borrow();
+
+        if parser.remaining().is_empty() {
+            let aliases = self.aliases.borrow();
+            let dynamic_commands = self.dynamic_commands.borrow();
+            let builtins = self.builtins.borrow();
+            let mut names = commands
+                .keys()
+                .chain(aliases.keys())
+                .copied()
+                .filter(|command| self.is_enabled(command))
+                .chain(dynamic_commands.keys().map(String::as_str))
+                .chain(builtins.iter().map(|builtin| builtin.name()))
+                .filter(|command| match self.config.suggestion_ranking {
+                    completion::SuggestionRanking::Fuzzy => {
+                        completion::fuzzy_score(command, name).is_some()
+                    }
+                    _ => command.starts_with(name),
+                })
+                .collect::<Vec<_>>();
+            names.sort();
+            names.dedup();
+            match self.config.suggestion_ranking {
+                completion::SuggestionRanking::Frequency => {
+                    names.sort_by_key(|command| Reverse(self.usage_count(command)))
+                }
+                completion::SuggestionRanking::Fuzzy => {
+                    names.sort_by_key(|command| Reverse(completion::fuzzy_score(command, name)))
+                }
+                completion::SuggestionRanking::Natural if partial.is_empty() => {
+                    self.usage.borrow().rank(&mut names)
+                }
+                _ => {}
+            }
+            return names
+                .into_iter()
+                .map(|command| {
+                    completion::Suggestion::new(command, 0, completion::SuggestionKind::Command)
+                })
+                .collect();
+        }
+
+        let Some(command) = commands
+            .get(self.resolve(name))
+            .filter(|command| command.enabled)
+        else {
+            return match self.dynamic_command(name) {
+                Some(command) => command.syntax.complete(&self.arguments(), parser),
+                None => Vec::new(),
+            };
+        };
+        let registry = self.arguments();
+        let mut suggestions = Vec::new();
+        for variant in command.usage.variants {
+            for suggestion in completion::complete_variant(variant, parser.branch(), &registry) {
+                if !suggestions.contains(&suggestion) {
+                    suggestions.push(suggestion);
+                }
+            }
+        }
+        if self.config.suggestion_ranking == completion::SuggestionRanking::Alphabetical {
+            suggestions.sort_by(|a, b| a.text.cmp(&b.text));
+        }
+        suggestions
+    }
+
+    /// Find the variant of a command matching the string in `command` and parse its arguments.
+    fn parse<'a>(&'a self, command: &'a str) -> Result<Execute<'a, Context>, CommandError<'a>> {
+        let input = command;
+        let mut parser = self.parser(command);
+        let command = (self.config.command_name)(&mut parser);
+        if let Some(dynamic) = self.dynamic_command(command) {
+            let arguments = dynamic
+                .syntax
+                .parse(&self.arguments.borrow(), &mut parser)
+                .map_err(CommandError::Parse)?;
+            return Ok(Execute::Sync(Box::new(move |context| {
+                (dynamic.execute)(context, arguments)
+            })));
+        }
+        let registered = self
+            .enabled_command(command)
+            .map(|command| (command.usage, command.dispatchers, command.flagless));
+        let Some((usage, dispatchers, flagless)) = registered else {
+            let builtin = self
+                .builtins
+                .borrow()
+                .iter()
+                .copied()
+                .find(|builtin| builtin.name() == command);
+            let fallback = self.fallback.borrow().clone();
+            return match (builtin, fallback) {
+                (Some(builtin), _) => self
+                    .parse_builtin(builtin, &mut parser)
+                    .map_err(CommandError::Parse),
+                (None, Some(fallback)) => {
+                    let input = input.to_string();
+                    Ok(Execute::Sync(Box::new(move |context| {
+                        fallback(context, &input)
+                    })))
+                }
+                (None, None) => Err(CommandError::Parse(
+                    parser.error(parser::ParseErrorKind::UnknownCommand),
+                )),
+            };
+        };
+
+        let check = self.permission_check.borrow().clone();
+        if let Some(check) = check {
+            let handle = self.context()?;
+            if !check(&*Self::borrow_context(&*handle)?, usage) {
+                return Err(CommandError::PermissionDenied);
+            }
+        }
+
+        let rest = parser.remaining();
+        let help = rest == "?" || rest.starts_with(char::is_whitespace) && rest.trim() == "--help";
+        if self.config.help_suffix && help && !flagless {
+            let output = self.output();
+            let help = usage.render(self.config.help_width.unwrap_or(usize::MAX));
+            return Ok(Execute::Sync(Box::new(move |_| {
+                output(&help);
+                Ok(0)
+            })));
+        }
 
         let mut last_error = None;
 
-        for dispatch in command.dispatchers {
+        for dispatch in dispatchers {
             let mut branch = parser.branch();
             match (dispatch.parser)(&mut branch) {
                 Ok(execute) => {
-                    return (execute)(&self.context);
+                    return Ok(execute);
                 }
                 Err(error) => {
+                    if parser.budget_exceeded() {
+                        return Err(CommandError::Parse(error));
+                    }
                     last_error = Some(error);
                 }
             }
         }
 
-        Err(CommandError::Parse(
-            last_error.expect("Expected at least one dispatch"),
-        ))
+        let failures = usage
+            .variants
+            .iter()
+            .filter_map(|variant| {
+                Some((
+                    variant,
+                    completion::expect_variant(variant, parser.branch())?,
+                ))
+            })
+            .collect::<Vec<_>>();
+        #[cfg(all(debug_assertions, feature = "ambiguity-check"))]
+        Self::check_ambiguity(usage, input, &failures);
+        let mut expected = parser::ExpectedSet::default();
+        for (_, (offset, items)) in failures {
+            for item in items {
+                expected.insert(offset, item);
+            }
+        }
+        Err(last_error.map_or(CommandError::NoVariants, |error| {
+            CommandError::Parse(error.with_expected(expected))
+        }))
+    }
+
+    /// Panic if two variants of a command fail at the same furthest position while expecting different arguments,
+    /// in which case the error reported for `input` depends on the order of the variants.
+    #[cfg(all(debug_assertions, feature = "ambiguity-check"))]
+    fn check_ambiguity(
+        usage: &CommandUsage,
+        input: &str,
+        failures: &[(&VariantUsage, (usize, Vec<parser::Expected>))],
+    ) {
+        let Some(furthest) = failures.iter().map(|(_, (offset, _))| *offset).max() else {
+            return;
+        };
+        let mut arguments = failures
+            .iter()
+            .filter(|(_, (offset, _))| *offset == furthest)
+            .filter_map(|(variant, (_, items))| match items.first() {
+                Some(parser::Expected::Argument(hint)) => Some((variant, hint)),
+                _ => None,
+            });
+        let Some((first, first_hint)) = arguments.next() else {
+            return;
+        };
+        if let Some((second, _)) = arguments.find(|(_, hint)| hint != &first_hint) {
+            panic!(
+                "Ambiguous error for `{}`: `{} {}` and `{} {}` both fail at column {} expecting different arguments",
+                input,
+                usage.name,
+                first.usage,
+                usage.name,
+                second.usage,
+                input[..furthest].chars().count() + 1
+            );
+        }
+    }
+
+    /// Strip the engine flags trailing `command`, unless the command it names is flagless, see `set_flagless`.
+    fn strip_flags<'c>(
+        &self,
+        command: &'c str,
+        options: &mut dispatch::DispatchOptions,
+    ) -> &'c str {
+        if self.is_flagless(command) {
+            command
+        } else {
+            dispatch::strip_flags(command, &self.config.engine_flags, options)
+        }
+    }
+
+    /// Whether the command named in `command` is flagless, see `set_flagless`.
+    fn is_flagless(&self, command: &str) -> bool {
+        let mut parser = self.parser(command);
+        let name = (self.config.command_name)(&mut parser);
+        self.commands
+            .borrow()
+            .get(self.resolve(name))
+            .is_some_and(|command| command.flagless)
+    }
+
+    /// Run the preprocessor of the command in `command`, if it has one.
+    fn preprocess<'a>(&self, command: &'a str) -> Cow<'a, str> {
+        let mut parser = self.parser(command);
+        let name = (self.config.command_name)(&mut parser);
+        let preprocessor = self
+            .enabled_command(name)
+            .and_then(|command| command.preprocessor);
+        match preprocessor {
+            Some(preprocessor) => {
+                let remaining = preprocessor(parser.remaining());
+                if remaining == parser.remaining() {
+                    Cow::Borrowed(command)
+                } else {
+                    Cow::Owned(format!("{}{}", name, remaining))
+                }
+            }
+            None => Cow::Borrowed(command),
+        }
+    }
+
+    /// Dispatch a command described by the string in `command`.
+    /// Fails with `CommandError::RequiresMutableContext` for variants taking `&mut Context`
+    /// and with `CommandError::RequiresAsync` for async variants.
+    pub fn dispatch<'a>(&'a self, command: &'a str) -> CommandResult<'a> {
+        self.dispatch_with(command, Default::default()).result
+    }
+
+    /// Dispatch a command like `dispatch`, using the given options.
+    pub fn dispatch_with<'a>(
+        &'a self,
+        command: &'a str,
+        mut options: dispatch::DispatchOptions,
+    ) -> dispatch::Dispatched<'a> {
+        #[cfg(feature = "tracing")]
+        let _span = self.span(command, &options).entered();
+        let result = match self.expand_history(command) {
+            Ok(Cow::Borrowed(command)) => self.run(command, &mut options),
+            Ok(Cow::Owned(command)) => self
+                .run(&command, &mut options)
+                .map_err(CommandError::into_owned),
+            Err(error) => Err(CommandError::Parse(error)),
+        };
+        self.notify(command, &options, &result);
+        dispatch::Dispatched {
+            result,
+            correlation_id: options.correlation_id,
+            flags: options.flags,
+        }
+    }
+
+    /// Strip the engine flags from an expanded command, then preprocess and execute it.
+    fn run<'a>(
+        &'a self,
+        command: &'a str,
+        options: &mut dispatch::DispatchOptions,
+    ) -> CommandResult<'a> {
+        let stripped = self.strip_flags(command, options);
+        match self.preprocess(stripped) {
+            Cow::Borrowed(command) => self.execute(command),
+            Cow::Owned(command) => self.execute(&command).map_err(CommandError::into_owned),
+        }
+    }
+
+    fn execute<'a>(&'a self, command: &'a str) -> CommandResult<'a> {
+        let execute = self.parse(command)?;
+        let handle = self.context()?;
+        let context = Self::borrow_context(&*handle)?;
+        let _output = OutputScope::enter(self.output());
+        match execute {
+            Execute::Sync(execute) => (execute)(&context),
+            Execute::Mut(_) => Err(CommandError::RequiresMutableContext),
+            #[cfg(feature = "async")]
+            Execute::Async(_) => Err(CommandError::RequiresAsync),
+        }
+    }
+
+    /// Dispatch a command received as raw bytes, e.g. from a socket.
+    /// The bytes are decoded according to the configured `EncodingPolicy`,
+    /// with line endings normalized and a single trailing line ending removed.
+    pub fn dispatch_bytes(&self, command: &[u8]) -> CommandResult<'static> {
+        let command = parser::decode(command, &self.config).map_err(CommandError::Parse)?;
+        self.dispatch(&command).map_err(CommandError::into_owned)
+    }
+
+    /// Dispatch a command given as already tokenized arguments, e.g. from `std::env::args` or an IPC message.
+    /// The arguments are joined with spaces, quoting and escaping those that contain whitespace, quotes or backslashes
+    /// or are empty, so they don't have to be re-escaped by hand. Arguments that need no quoting are passed unchanged,
+    /// so with `QuotingPolicy::Always` string arguments still have to be quoted by the caller.
+    /// Quoted arguments are read back as strings, so they only round-trip for `String` parameters:
+    /// e.g. `" "` is passed as `" "`, which `char` and `Word` reject.
+    /// Arguments are never taken for engine flags, so they can't be given this way, see `ParserConfig::engine_flags`.
+    pub fn dispatch_argv<S: AsRef<str>>(&self, argv: &[S]) -> CommandResult<'static> {
+        let mut command = parser::join_argv(argv);
+        if !self.config.engine_flags.is_empty() && !self.is_flagless(&command) {
+            // Ends the arguments, so that none of them is stripped as a flag
+            command.push_str(" --");
+        }
+        self.dispatch(&command).map_err(CommandError::into_owned)
+    }
+
+    /// Dispatch several commands separated by `;` or `&&`, returning the result of the last command that ran.
+    /// The separators can be changed using `ParserConfig::chain_separators`, e.g. to enable piping with `|`.
+    /// Commands following `&&` only run if the previous command succeeded.
+    /// Commands following `|` also only run if the previous command succeeded, and receive the text it wrote to the output
+    /// as a quoted string in their final argument instead of it being written to the output, one line per write.
+    /// The text is inserted before any engine flags the command ends with.
+    /// E.g. if `list players` writes `alex` and `sam`, `list players | count` runs `count "alex\nsam"`.
+    pub fn dispatch_chain(&self, commands: &str) -> CommandResult<'static> {
+        let commands = chain::split(commands, &self.config.chain_separators);
+        let mut result = Ok(0);
+        let mut piped = String::new();
+        for (index, &(separator, command)) in commands.iter().enumerate() {
+            let pipes = commands
+                .get(index + 1)
+                .is_some_and(|&(next, _)| next == Some(chain::Separator::Pipe));
+            let command = match separator {
+                Some(chain::Separator::Pipe) => {
+                    // The piped text goes before any engine flags, which are only stripped from the end
+                    let arguments = self.strip_flags(command, &mut Default::default());
+                    let flags = &command[arguments.len()..];
+                    Cow::Owned(format!("{} {}{}", arguments, parser::quote(&piped), flags))
+                }
+                _ => Cow::Borrowed(command),
+            };
+            result = match (separator, result) {
+                (Some(chain::Separator::And | chain::Separator::Pipe), Err(error)) => Err(error),
+                _ if pipes => {
+                    let (result, output) = self.capture_output(|| {
+                        self.dispatch(&command).map_err(CommandError::into_owned)
+                    });
+                    piped = output;
+                    result
+                }
+                _ => self.dispatch(&command).map_err(CommandError::into_owned),
+            };
+        }
+        result
+    }
+
+    /// Run `f` while collecting the text written to the output instead of passing it on, one line per write.
+    /// The previous output is restored even if `f` panics.
+    fn capture_output<T>(&self, f: impl FnOnce() -> T) -> (T, String) {
+        let captured = Rc::new(RefCell::new(Vec::<String>::new()));
+        let capture = captured.clone();
+        let previous = self.output.replace(Some(Rc::new(move |text: &str| {
+            capture.borrow_mut().push(text.to_string())
+        })));
+        let restore = RestoreOutput {
+            output: &self.output,
+            previous,
+        };
+        let result = f();
+        drop(restore);
+        let output = captured.borrow().join("\n");
+        (result, output)
+    }
+
+    /// Dispatch a command described by the string in `command`, allowing it to mutate the context.
+    /// Fails with `CommandError::RequiresAsync` for async variants.
+    pub fn dispatch_mut<'a>(&'a self, command: &'a str) -> CommandResult<'a> {
+        self.dispatch_mut_with(command, Default::default()).result
+    }
+
+    /// Dispatch a command like `dispatch_mut`, using the given options.
+    pub fn dispatch_mut_with<'a>(
+        &'a self,
+        command: &'a str,
+        mut options: dispatch::DispatchOptions,
+    ) -> dispatch::Dispatched<'a> {
+        #[cfg(feature = "tracing")]
+        let _span = self.span(command, &options).entered();
+        let result = match self.expand_history(command) {
+            Ok(Cow::Borrowed(command)) => self.run_mut(command, &mut options),
+            Ok(Cow::Owned(command)) => self
+                .run_mut(&command, &mut options)
+                .map_err(CommandError::into_owned),
+            Err(error) => Err(CommandError::Parse(error)),
+        };
+        self.notify(command, &options, &result);
+        dispatch::Dispatched {
+            result,
+            correlation_id: options.correlation_id,
+            flags: options.flags,
+        }
+    }
+
+    /// Strip the engine flags from an expanded command, then preprocess and execute it.
+    fn run_mut<'a>(
+        &'a self,
+        command: &'a str,
+        options: &mut dispatch::DispatchOptions,
+    ) -> CommandResult<'a> {
+        let stripped = self.strip_flags(command, options);
+        match self.preprocess(stripped) {
+            Cow::Borrowed(command) => self.execute_mut(command),
+            Cow::Owned(command) => self.execute_mut(&command).map_err(CommandError::into_owned),
+        }
+    }
+
+    fn execute_mut<'a>(&'a self, command: &'a str) -> CommandResult<'a> {
+        let execute = self.parse(command)?;
+        let handle = self.context()?;
+        let _output = OutputScope::enter(self.output());
+        match execute {
+            Execute::Sync(execute) => (execute)(&*Self::borrow_context(&*handle)?),
+            Execute::Mut(execute) => {
+                let mut context = handle
+                    .try_borrow_context_mut()
+                    .ok_or(CommandError::ContextBorrowed)?;
+                (execute)(&mut context)
+            }
+            #[cfg(feature = "async")]
+            Execute::Async(_) => Err(CommandError::RequiresAsync),
+        }
+    }
+
+    fn context<'a>(&self) -> Result<Rc<dyn ContextCell<Context>>, CommandError<'a>> {
+        (self.context)().ok_or(CommandError::ContextUnavailable)
+    }
+
+    fn borrow_context<'a>(
+        context: &dyn ContextCell<Context>,
+    ) -> Result<Ref<'_, Context>, CommandError<'a>> {
+        context
+            .try_borrow_context()
+            .ok_or(CommandError::ContextBorrowed)
+    }
+
+    /// Dispatch a command described by the string in `command`, awaiting it if it is asynchronous.
+    /// Async commands take a reference to the context that lives across their `.await`s,
+    /// so the context stays borrowed until the command completes, including while it is suspended.
+    /// In the meantime, `dispatch_mut` fails with `CommandError::ContextBorrowed`, as do mutable commands of sources
+    /// created by `adapt`.
+    /// Long-running commands that should not block mutations have to copy what they need out of the context
+    /// and continue with a spawned task instead.
+    /// Fails with `CommandError::ShuttingDown` once `shutdown` has been called.
+    #[cfg(feature = "async")]
+    pub async fn dispatch_async<'a>(&'a self, command: &'a str) -> CommandResult<'a> {
+        self.dispatch_async_with(command, Default::default())
+            .await
+            .result
+    }
+
+    /// Dispatch a command like `dispatch_async`, using the given options.
+    #[cfg(feature = "async")]
+    pub async fn dispatch_async_with<'a>(
+        &'a self,
+        command: &'a str,
+        mut options: dispatch::DispatchOptions,
+    ) -> dispatch::Dispatched<'a> {
+        #[cfg(feature = "tracing")]
+        let span = self.span(command, &options);
+        let dispatch = async {
+            let result = match self.expand_history(command) {
+                Ok(Cow::Borrowed(command)) => self.dispatch_tracked(command, &mut options).await,
+                Ok(Cow::Owned(command)) => self
+                    .dispatch_tracked(&command, &mut options)
+                    .await
+                    .map_err(CommandError::into_owned),
+                Err(error) => Err(CommandError::Parse(error)),
+            };
+            self.notify(command, &options, &result);
+            result
+        };
+        #[cfg(feature = "tracing")]
+        let dispatch = tracing::Instrument::instrument(dispatch, span);
+        let result = dispatch.await;
+        dispatch::Dispatched {
+            result,
+            correlation_id: options.correlation_id,
+            flags: options.flags,
+        }
+    }
+
+    /// Dispatch an expanded command, keeping track of it until it completes for `shutdown`.
+    #[cfg(feature = "async")]
+    async fn dispatch_tracked<'a>(
+        &'a self,
+        command: &'a str,
+        options: &mut dispatch::DispatchOptions,
+    ) -> CommandResult<'a> {
+        let command = self.strip_flags(command, options);
+        let ticket = self
+            .in_flight
+            .start(command)
+            .ok_or(CommandError::ShuttingDown)?;
+        match self.preprocess(command) {
+            Cow::Borrowed(command) => self.execute_cancellable(command, &ticket).await,
+            Cow::Owned(command) => self
+                .execute_cancellable(&command, &ticket)
+                .await
+                .map_err(CommandError::into_owned),
+        }
+    }
+
+    /// Execute a command like `execute_async`, unless it is cancelled while shutting down.
+    /// The output of this source is the target of `write_output` whenever the command is polled,
+    /// so other commands running concurrently on the same thread write to their own sources' outputs.
+    #[cfg(feature = "async")]
+    async fn execute_cancellable<'a>(
+        &'a self,
+        command: &'a str,
+        ticket: &shutdown::Ticket<'_>,
+    ) -> CommandResult<'a> {
+        use std::future::Future;
+
+        let output = self.output();
+        let mut execution = std::pin::pin!(self.execute_async(command));
+        std::future::poll_fn(|cx| {
+            if ticket.cancelled(cx) {
+                return std::task::Poll::Ready(Err(CommandError::Cancelled));
+            }
+            let _output = OutputScope::enter(output.clone());
+            execution.as_mut().poll(cx)
+        })
+        .await
+    }
+
+    /// Stop accepting commands through `dispatch_async` and wait for the running ones to complete
+    /// until `timeout` does, e.g. `tokio::time::sleep(Duration::from_secs(5))`.
+    /// Commands still running afterwards are cancelled, failing with `CommandError::Cancelled`,
+    /// and listed in the returned report.
+    /// As commands dispatched by the awaiting task are waited for as well, don't await this from within a command;
+    /// have the command signal the host to shut down instead.
+    #[cfg(feature = "async")]
+    pub async fn shutdown(
+        &self,
+        timeout: impl std::future::Future<Output = ()>,
+    ) -> shutdown::ShutdownReport {
+        self.in_flight.shutdown(timeout).await
+    }
+
+    #[cfg(feature = "async")]
+    #[expect(
+        clippy::await_holding_refcell_ref,
+        reason = "async commands borrow the context until they complete, as documented on `dispatch_async`"
+    )]
+    async fn execute_async<'a>(&'a self, command: &'a str) -> CommandResult<'a> {
+        let execute = self.parse(command)?;
+        let handle = self.context()?;
+        match execute {
+            Execute::Sync(execute) => (execute)(&*Self::borrow_context(&*handle)?),
+            Execute::Mut(_) => Err(CommandError::RequiresMutableContext),
+            Execute::Async(execute) => (execute)(&*Self::borrow_context(&*handle)?).await,
+        }
     }
 }
 
-/// Helper macro to register a command to a `CommandSource`.
+/// Helper macro to register a command to a `CommandSource`, along with its aliases.
 #[macro_export]
 macro_rules! register_command {
-    ($source:expr, $name:ident) => {
-        ($source).register(stringify!($name), &$name::USAGE, $name::DISPATCHERS)
-    };
+    ($source:expr, $name:ident) => {{
+        let source = &$source;
+        source.register_spec(&$name::SPEC);
+        for alias in $name::ALIASES {
+            source.register_alias(alias, stringify!($name));
+        }
+    }};
 }
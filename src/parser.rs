@@ -1,21 +1,287 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    fmt::{self, Display, Formatter},
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+    },
+    rc::Rc,
+    str::FromStr,
+};
+
+use crate::{
+    chain::{self, Separator},
+    completion::SuggestionRanking,
+    dispatch::EngineFlag,
+};
+
+/// Settings affecting how commands and their arguments are parsed.
+#[derive(Debug, Clone)]
+pub struct ParserConfig {
+    pub quoting: QuotingPolicy,
+    pub encoding: EncodingPolicy,
+    /// The maximum number of characters examined while parsing a single command, across all of its variants.
+    /// Once exceeded, parsing fails with `ParseErrorKind::TooComplex`. Unlimited if `None`.
+    pub budget: Option<usize>,
+    /// Reads the name of the command at the start of the input. Defaults to `alphabetic_name`.
+    pub command_name: CommandNameReader,
+    /// Whether float arguments accept `inf`, `infinity` and `nan`, ignoring case.
+    pub special_floats: bool,
+    /// A character that may be used to group the digits of numeric arguments, e.g. `_` in `1_000_000` or `,` in `1,000`.
+    /// Overridden for individual arguments by the `digit_separator` attribute of `define_command!`.
+    /// Commas can't be told apart from the separators of `arguments::List`, so they shouldn't be combined,
+    /// while the compact form of `arguments::Region` and the weights of `arguments::WeightedList` never group digits by commas.
+    /// Disabled by default.
+    pub digit_separator: Option<char>,
+    /// The words accepted as `true` by `bool` arguments, matched ignoring case.
+    /// Defaults to `true`, `on`, `yes`, `enable` and `1`.
+    pub true_words: Vec<String>,
+    /// The words accepted as `false` by `bool` arguments, matched ignoring case.
+    /// Defaults to `false`, `off`, `no`, `disable` and `0`.
+    pub false_words: Vec<String>,
+    /// The namespace of `Identifier` arguments written without one, e.g. `Some("minecraft".into())` to read `stone`
+    /// as `minecraft:stone`. Defaults to `None`, in which case identifiers have to include their namespace.
+    pub default_namespace: Option<String>,
+    /// Whether a command followed by `?` or `--help`, e.g. `tp?`, writes the command's help to the output
+    /// instead of being parsed. Enabled by default. Commands marked with `CommandSource::set_flagless` are always parsed.
+    pub help_suffix: bool,
+    /// The width in characters the help written for `?` and `--help` is fitted into, see `CommandUsage::render`.
+    /// Unlimited if `None`.
+    pub help_width: Option<usize>,
+    /// The maximum number of suggestions returned by `CommandSource::complete`. Unlimited if `None`.
+    pub suggestion_limit: Option<usize>,
+    /// How suggestions returned by `CommandSource::complete` are ordered, which also decides which are cut off by the limit.
+    pub suggestion_ranking: SuggestionRanking,
+    /// The separators splitting the input of `CommandSource::dispatch_chain` into commands, along with their meaning.
+    /// Defaults to `chain::default_separators`, i.e. `;` and `&&`. Piping with `|` has to be added explicitly.
+    /// For chat platforms where semicolons are common in messages,
+    /// only splitting at line breaks may be preferable, e.g. `vec![("\n".into(), Separator::Sequence)]`.
+    pub chain_separators: Vec<(String, Separator)>,
+    /// Flags any command may be followed by, e.g. `--silent` or `--as <player>`.
+    /// They are stripped from the end of the command before it is parsed and passed on in `DispatchOptions::flags`,
+    /// so cross-cutting modifiers don't need to be part of every command's grammar. Values can't contain whitespace.
+    /// Negative numbers like `-5` are never taken for flags. A standalone `--` ends the command's own arguments:
+    /// only the flags following it are stripped, and it is removed itself, so `say --silent --` says `--silent`.
+    /// Use `CommandSource::set_flagless` for commands that should never be searched for flags.
+    pub engine_flags: Vec<EngineFlag>,
+    /// Whether `!!` in a dispatched command is replaced with the previous command and `!n` with the command numbered `n`
+    /// in `CommandSource::history`, like in a shell. References within quoted strings are kept as-is.
+    pub history_expansion: bool,
+    /// The number of commands remembered in `CommandSource::history`, e.g. 100.
+    /// Defaults to 0, in which case no history is kept, so it has to be set for `Builtin::History` and `history_expansion`.
+    pub history_size: usize,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            quoting: Default::default(),
+            encoding: Default::default(),
+            budget: None,
+            command_name: alphabetic_name,
+            special_floats: false,
+            digit_separator: None,
+            true_words: ["true", "on", "yes", "enable", "1"]
+                .map(String::from)
+                .to_vec(),
+            false_words: ["false", "off", "no", "disable", "0"]
+                .map(String::from)
+                .to_vec(),
+            default_namespace: None,
+            help_suffix: true,
+            help_width: None,
+            suggestion_limit: None,
+            suggestion_ranking: Default::default(),
+            chain_separators: chain::default_separators(),
+            engine_flags: Vec::new(),
+            history_expansion: false,
+            history_size: 0,
+        }
+    }
+}
+
+/// Reads the name of a command, see `ParserConfig::command_name`.
+pub type CommandNameReader = for<'a> fn(&mut CommandParser<'a>) -> &'a str;
+
+/// Read a command name consisting of alphabetic characters.
+pub fn alphabetic_name<'a>(parser: &mut CommandParser<'a>) -> &'a str {
+    parser.read_while(char::is_alphabetic)
+}
+
+/// Read a single ASCII punctuation character such as `?` or `!` as a command name,
+/// or a name consisting of alphabetic characters otherwise.
+/// This allows registering shorthand commands like `?` for help.
+pub fn sigil_or_alphabetic_name<'a>(parser: &mut CommandParser<'a>) -> &'a str {
+    let mut first = true;
+    let sigil = parser.read_while(|c| std::mem::take(&mut first) && c.is_ascii_punctuation());
+    if sigil.is_empty() {
+        alphabetic_name(parser)
+    } else {
+        sigil
+    }
+}
+
+/// Determines when `String` arguments have to be enclosed in double quotes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuotingPolicy {
+    /// Strings always have to be quoted.
+    #[default]
+    Always,
+    /// Single words may be given without quotes, while strings containing whitespace have to be quoted.
+    /// Quotes may not appear within unquoted words.
+    Strict,
+}
+
+/// Determines how invalid UTF-8 is handled when dispatching raw bytes, see `CommandSource::dispatch_bytes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncodingPolicy {
+    /// Invalid UTF-8 is rejected with `ParseErrorKind::InvalidEncoding`.
+    #[default]
+    Strict,
+    /// Invalid sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    Lossy,
+}
+
+/// Decode a command received as raw bytes according to the configured `EncodingPolicy`.
+/// Line endings are normalized to `\n` and a single trailing line ending is removed.
+pub(crate) fn decode(bytes: &[u8], config: &ParserConfig) -> Result<String, ParseError<'static>> {
+    let command = match std::str::from_utf8(bytes) {
+        Ok(command) => Cow::Borrowed(command),
+        Err(_) if config.encoding == EncodingPolicy::Lossy => String::from_utf8_lossy(bytes),
+        Err(error) => {
+            return Err(ParseError {
+                command: Cow::Owned(String::from_utf8_lossy(bytes).into_owned()),
+                offset: error.valid_up_to(),
+                kind: ParseErrorKind::InvalidEncoding,
+                expected: None,
+            })
+        }
+    };
+    let command = command.replace("\r\n", "\n").replace('\r', "\n");
+    Ok(match command.strip_suffix('\n') {
+        Some(command) => command.to_string(),
+        None => command,
+    })
+}
+
+/// Join already tokenized arguments into a single command, separated by spaces.
+/// Arguments that are empty or contain whitespace, quotes or backslashes are enclosed in double quotes,
+/// with quotes and backslashes escaped, so that each of them is read back as a single string.
+pub(crate) fn join_argv<S: AsRef<str>>(argv: &[S]) -> String {
+    let mut command = String::new();
+    for (index, argument) in argv.iter().enumerate() {
+        let argument = argument.as_ref();
+        if index > 0 {
+            command.push(' ');
+        }
+        if !argument.is_empty()
+            && !argument.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\')
+        {
+            command.push_str(argument);
+        } else {
+            command.push_str(&quote(argument));
+        }
+    }
+    command
+}
+
+/// Enclose text in double quotes, escaping quotes and backslashes, so that it is read back as a single string.
+pub(crate) fn quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The remaining parse budget, shared between a parser and its branches.
+struct Budget {
+    remaining: Cell<Option<usize>>,
+    exceeded: Cell<bool>,
+}
+
+impl Budget {
+    /// Spend the given number of characters, returning `false` if the budget is exceeded.
+    fn spend(&self, n: usize) -> bool {
+        match self.remaining.get() {
+            None => true,
+            Some(remaining) if remaining >= n && !self.exceeded.get() => {
+                self.remaining.set(Some(remaining - n));
+                true
+            }
+            Some(_) => {
+                self.exceeded.set(true);
+                false
+            }
+        }
+    }
+}
+
+/// Looks up the values of a set of literals determined at dispatch time, see `CommandSource::register_literals`.
+pub(crate) type LiteralSource = Rc<dyn Fn(&str) -> Option<Vec<String>>>;
+
+/// A position in a command saved by `CommandParser::checkpoint`, which the parser can be reset to using `CommandParser::rollback`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    offset: usize,
+    /// The address of the command the checkpoint was taken from, identifying it along with its length.
+    command: usize,
+    /// The length of the command the checkpoint was taken from.
+    len: usize,
+}
 
 /// Helper to parse command syntax.
 pub struct CommandParser<'a> {
     command: &'a str,
     offset: usize,
+    config: Rc<ParserConfig>,
+    budget: Rc<Budget>,
+    literals: Option<LiteralSource>,
 }
 
 impl<'a> CommandParser<'a> {
-    /// Create a parser for the given command.
+    /// Create a parser for the given command using the default configuration.
     pub fn new(command: &'a str) -> Self {
-        Self { command, offset: 0 }
+        Self::with_config(command, Rc::default())
+    }
+
+    /// Create a parser for the given command using the given configuration.
+    pub fn with_config(command: &'a str, config: Rc<ParserConfig>) -> Self {
+        let budget = Budget {
+            remaining: Cell::new(config.budget),
+            exceeded: Cell::new(false),
+        };
+        Self {
+            command,
+            offset: 0,
+            config,
+            budget: Rc::new(budget),
+            literals: None,
+        }
+    }
+
+    /// Look up literal sets using the given source.
+    pub(crate) fn with_literals(self, literals: LiteralSource) -> Self {
+        Self {
+            literals: Some(literals),
+            ..self
+        }
+    }
+
+    /// The configuration used by this parser.
+    pub fn config(&self) -> &ParserConfig {
+        &self.config
     }
 
     /// Match the given literal to the command, advancing the parser if successful.
     /// Returns an error if the literal does not match.
     pub fn lit(&mut self, lit: &str) -> Result<(), ParseError<'a>> {
-        if self.command[self.offset..].starts_with(lit) {
+        if self.budget.spend(lit.chars().count()) && self.command[self.offset..].starts_with(lit) {
             self.offset += lit.len();
             Ok(())
         } else {
@@ -23,12 +289,50 @@ impl<'a> CommandParser<'a> {
         }
     }
 
+    /// Get the values of the literal set with the given name, which are determined at dispatch time.
+    /// Returns nothing if there is no such set.
+    pub fn literals(&self, set: &str) -> Vec<String> {
+        self.literals
+            .as_ref()
+            .and_then(|literals| literals(set))
+            .unwrap_or_default()
+    }
+
+    /// Match a token against the values of the literal set with the given name, advancing the parser if successful.
+    /// Returns the matched value or an error if the token is not part of the set.
+    pub fn dynamic_lit(&mut self, set: &str) -> Result<String, ParseError<'a>> {
+        let start = self.branch();
+        let token = self.read_while(|c| !c.is_whitespace());
+        if self.literals(set).iter().any(|literal| literal == token) {
+            Ok(token.to_string())
+        } else {
+            Err(start.error(ParseErrorKind::BadLiteral))
+        }
+    }
+
     /// Parse an argument of the given type.
     /// See the `Argument` trait for more information.
     pub fn argument<A: Argument>(&mut self) -> Result<A, ParseError<'a>> {
         A::parse(self)
     }
 
+    /// Read a single token ending at the next whitespace and convert it using `FromStr`.
+    /// If the conversion fails, its error is reported as `ParseErrorKind::Custom`, see `arguments::FromStrArg`.
+    pub fn parse_token<T>(&mut self) -> Result<T, ParseError<'a>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let start = self.branch();
+        let token = self.read_while(|c| !c.is_whitespace());
+        if token.is_empty() {
+            return Err(self.error(ParseErrorKind::UnexpectedEof));
+        }
+        token
+            .parse()
+            .map_err(|error: T::Err| start.custom_error(error.to_string()))
+    }
+
     /// Advance the parser by the given number of characters.
     pub fn advance(&mut self, n: usize) {
         self.offset += n;
@@ -52,7 +356,7 @@ impl<'a> CommandParser<'a> {
     {
         let start = self.offset;
         while let Some(c) = self.command[self.offset..].chars().next() {
-            if f(c) {
+            if self.budget.spend(1) && f(c) {
                 self.offset += c.len_utf8();
             } else {
                 break;
@@ -61,15 +365,77 @@ impl<'a> CommandParser<'a> {
         &self.command[start..self.offset]
     }
 
-    /// Generate and return a `ParseError` at the current position.   
+    /// The next character of the command without advancing the parser, or `None` at the end of the command.
+    /// Peeking is not charged against the parse budget.
+    pub fn peek_char(&self) -> Option<char> {
+        self.command[self.offset..].chars().next()
+    }
+
+    /// The text up to the next whitespace without advancing the parser, which is empty at whitespace or the end of the command.
+    /// Peeking is not charged against the parse budget.
+    pub fn peek_word(&self) -> &'a str {
+        let rest = &self.command[self.offset..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        &rest[..end]
+    }
+
+    /// Whether the unconsumed part of the command starts with `prefix`, without advancing the parser.
+    /// Peeking is not charged against the parse budget.
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        self.command[self.offset..].starts_with(prefix)
+    }
+
+    /// The part of the command that has not been consumed yet.
+    pub fn remaining(&self) -> &'a str {
+        &self.command[self.offset..]
+    }
+
+    /// The part of the command that has already been consumed.
+    pub fn consumed(&self) -> &'a str {
+        &self.command[..self.offset]
+    }
+
+    /// The current position of the parser in the command, in bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Split the remaining input into classified tokens without advancing the parser,
+    /// e.g. for linting commands or debugging custom `Argument` implementations.
+    /// Tokens are not charged against the parse budget.
+    pub fn tokens(&self) -> Tokens<'a> {
+        Tokens {
+            command: self.command,
+            offset: self.offset,
+        }
+    }
+
+    /// Whether the parse budget set in the `ParserConfig` has been exceeded.
+    /// Once this is the case, no further input is read.
+    pub fn budget_exceeded(&self) -> bool {
+        self.budget.exceeded.get()
+    }
+
+    /// Generate and return a `ParseError` at the current position.
+    /// If the parse budget has been exceeded, the error is reported as `ParseErrorKind::TooComplex` instead.
     pub fn error(&self, kind: ParseErrorKind) -> ParseError<'a> {
         ParseError {
-            command: self.command,
+            command: Cow::Borrowed(self.command),
             offset: self.offset,
-            kind,
+            kind: if self.budget_exceeded() {
+                ParseErrorKind::TooComplex
+            } else {
+                kind
+            },
+            expected: None,
         }
     }
 
+    /// Generate a `ParseError` with a custom message at the current position, e.g. `parser.custom_error("expected a color like #ff0000")`.
+    pub fn custom_error(&self, message: impl Into<Cow<'static, str>>) -> ParseError<'a> {
+        self.error(ParseErrorKind::Custom(message.into()))
+    }
+
     /// Expect the end of the command.
     pub fn end(&mut self) -> Result<(), ParseError<'a>> {
         if self.offset == self.command.len() {
@@ -79,42 +445,301 @@ impl<'a> CommandParser<'a> {
         }
     }
 
+    /// Create a copy of this parser which ends after the next `len` bytes, e.g. to parse one side of an interval
+    /// or a single element of a list.
+    pub(crate) fn truncated(&self, len: usize) -> Self {
+        Self {
+            command: &self.command[..self.offset + len],
+            ..self.branch()
+        }
+    }
+
+    /// Move an error raised by a parser created with `truncated` onto the full command.
+    pub(crate) fn widen_error(&self, error: ParseError) -> ParseError<'a> {
+        ParseError {
+            command: Cow::Borrowed(self.command),
+            offset: error.offset,
+            kind: error.kind,
+            expected: error.expected,
+        }
+    }
+
+    /// Save the current position, e.g. to try several alternative syntaxes in turn.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            offset: self.offset,
+            command: self.command.as_ptr() as usize,
+            len: self.command.len(),
+        }
+    }
+
+    /// Reset the parser to a position saved by `checkpoint`, undoing everything consumed since.
+    /// The checkpoint has to be taken from this parser or a branch of it. Input read since is still charged against the parse budget.
+    ///
+    /// # Panics
+    /// Panics if the checkpoint was taken from a parser of a different command.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        assert!(
+            checkpoint.command == self.command.as_ptr() as usize
+                && checkpoint.len == self.command.len(),
+            "the checkpoint was taken from a different command"
+        );
+        self.offset = checkpoint.offset;
+    }
+
     /// Create a copy of this parser at the current position.
     pub fn branch(&self) -> Self {
         Self {
             command: self.command,
             offset: self.offset,
+            config: self.config.clone(),
+            budget: self.budget.clone(),
+            literals: self.literals.clone(),
         }
     }
 }
 
+/// What a `Token` looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A run of alphanumeric characters, `_`, `-` and `.` which isn't a number.
+    Word,
+    /// An integer or decimal number, possibly signed, e.g. `-12` or `0.5`.
+    Number,
+    /// A string enclosed in double quotes, including the quotes. Unterminated strings extend to the end of the input.
+    Quoted,
+    /// Any other single character, e.g. `~`, `@` or `,`.
+    Symbol,
+}
+
+/// A piece of input classified by `CommandParser::tokens`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// The position of the token in the command, in bytes.
+    pub span: std::ops::Range<usize>,
+}
+
+/// An iterator over the tokens of the remaining input, see `CommandParser::tokens`.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    command: &'a str,
+    offset: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.command[self.offset..];
+        let start = self.offset + (rest.len() - rest.trim_start().len());
+        let rest = &self.command[start..];
+        let mut chars = rest.chars();
+        let first = chars.next()?;
+        let signed_number =
+            matches!(first, '-' | '+') && chars.next().is_some_and(|c| c.is_ascii_digit());
+
+        let (kind, len) = if first == '"' {
+            let mut escaped = false;
+            let end = rest[1..].find(|c| {
+                let end = !escaped && c == '"';
+                escaped = !escaped && c == '\\';
+                end
+            });
+            (TokenKind::Quoted, end.map_or(rest.len(), |end| end + 2))
+        } else if first.is_alphanumeric() || first == '_' || signed_number {
+            let len = rest[first.len_utf8()..]
+                .find(|c| !is_word_char(c))
+                .map_or(rest.len(), |end| end + first.len_utf8());
+            let text = rest[..len].trim_start_matches(['-', '+']);
+            let number = text.starts_with(|c: char| c.is_ascii_digit())
+                && text.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && text.matches('.').count() <= 1;
+            let kind = if number {
+                TokenKind::Number
+            } else {
+                TokenKind::Word
+            };
+            (kind, len)
+        } else {
+            (TokenKind::Symbol, first.len_utf8())
+        };
+
+        self.offset = start + len;
+        Some(Token {
+            kind,
+            text: &self.command[start..self.offset],
+            span: start..self.offset,
+        })
+    }
+}
+
 /// An error that occurs during parsing.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseError<'a> {
-    command: &'a str,
+    command: Cow<'a, str>,
     offset: usize,
     pub kind: ParseErrorKind,
+    expected: Option<ExpectedSet>,
+}
+
+impl ParseError<'_> {
+    /// A stable, machine-readable code identifying the kind of error, see `ParseErrorKind::code`.
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    /// Copy the command into the error so it no longer borrows the input.
+    pub fn into_owned(self) -> ParseError<'static> {
+        ParseError {
+            command: Cow::Owned(self.command.into_owned()),
+            offset: self.offset,
+            kind: self.kind,
+            expected: self.expected,
+        }
+    }
+
+    /// What the variants of the command expected at the furthest position any of them reached, if known.
+    pub fn expected(&self) -> Option<&ExpectedSet> {
+        self.expected.as_ref()
+    }
+
+    /// Attach what the command expected to the error.
+    /// It is included in the message unless the kind of error is more specific, such as `ParseErrorKind::OutOfBounds`.
+    pub fn with_expected(mut self, expected: ExpectedSet) -> Self {
+        self.expected = (!expected.is_empty()).then_some(expected);
+        self
+    }
 }
 
 impl Display for ParseError<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let start = self.offset.saturating_sub(10);
-        let end = self.offset + 10;
+        let mut start = self.offset.saturating_sub(10);
+        while !self.command.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut end = (self.offset + 10).min(self.command.len());
+        while !self.command.is_char_boundary(end) {
+            end += 1;
+        }
         let command = &self.command[start..end];
-        match self.kind {
+        match &self.kind {
             ParseErrorKind::UnknownCommand => write!(f, "Unknown command: `{}`", command),
             ParseErrorKind::UnexpectedEof => write!(f, "Unexpected end of command"),
             ParseErrorKind::ExpectedEof => write!(f, "Expected end of command"),
             ParseErrorKind::BadArgument => write!(f, "Bad argument"),
             ParseErrorKind::BadLiteral => write!(f, "Bad literal"),
             ParseErrorKind::ExpectedWhitespace => write!(f, "Expected whitespace"),
+            ParseErrorKind::ExpectedQuote => {
+                write!(f, "Expected `\"`: strings have to be enclosed in double quotes")
+            }
+            ParseErrorKind::UnterminatedString => {
+                write!(f, "Unterminated string: missing closing `\"`")
+            }
+            ParseErrorKind::InvalidEncoding => write!(f, "Invalid UTF-8 in command"),
+            ParseErrorKind::TooComplex => write!(f, "Command is too complex to parse"),
+            ParseErrorKind::OutOfBounds { min, max } => {
+                write!(f, "Value out of bounds: expected {} to {}", min, max)
+            }
+            ParseErrorKind::Zero => write!(f, "Value must not be zero"),
+            ParseErrorKind::Custom(message) => write!(f, "{}", message),
+            ParseErrorKind::UnexpectedQuote => write!(
+                f,
+                "Unexpected `\"`: strings containing whitespace have to be enclosed in double quotes entirely"
+            ),
+        }?;
+        match &self.expected {
+            Some(expected) if self.kind.is_generic() => {
+                let mut offset = expected.offset().min(self.command.len());
+                while !self.command.is_char_boundary(offset) {
+                    offset -= 1;
+                }
+                let column = self.command[..offset].chars().count() + 1;
+                write!(f, ": at column {} {}", column, expected)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Something a command could have continued with at a certain position.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expected {
+    Literal(String),
+    /// An argument, described like `<count: u32>`.
+    Argument(String),
+    /// The end of the input.
+    End,
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Literal(text) | Expected::Argument(text) => write!(f, "`{}`", text),
+            Expected::End => write!(f, "end of input"),
         }
     }
 }
 
+/// Everything the variants of a command expected at the furthest position any of them reached.
+/// Displayed like ``expected `<count: u32>`, `all`, or end of input``.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpectedSet {
+    offset: usize,
+    items: Vec<Expected>,
+}
+
+impl ExpectedSet {
+    /// The byte offset into the command the items are expected at.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn items(&self) -> &[Expected] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Add an item expected at the given offset.
+    /// Items at a further offset replace the current ones, while items before them are ignored.
+    pub fn insert(&mut self, offset: usize, item: Expected) {
+        if self.items.is_empty() || offset > self.offset {
+            self.offset = offset;
+            self.items.clear();
+        }
+        if offset == self.offset && !self.items.contains(&item) {
+            self.items.push(item);
+        }
+    }
+}
+
+impl Display for ExpectedSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "expected ")?;
+        for (i, item) in self.items.iter().enumerate() {
+            match i {
+                0 => {}
+                _ if i + 1 < self.items.len() => write!(f, ", ")?,
+                1 => write!(f, " or ")?,
+                _ => write!(f, ", or ")?,
+            }
+            write!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
 impl std::error::Error for ParseError<'_> {}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParseErrorKind {
     /// The given command name has no command associated with it.
     UnknownCommand,
@@ -128,6 +753,58 @@ pub enum ParseErrorKind {
     BadLiteral,
     /// The parser expected whitespaces
     ExpectedWhitespace,
+    /// The parser expected a quoted string.
+    ExpectedQuote,
+    /// A quoted string is missing its closing quote.
+    UnterminatedString,
+    /// An unquoted string contains a quote.
+    UnexpectedQuote,
+    /// The command is not valid UTF-8.
+    InvalidEncoding,
+    /// Parsing the command exceeded the parse budget set in the `ParserConfig`.
+    TooComplex,
+    /// A numeric argument is outside of the range it is restricted to, see `arguments::Bounded`.
+    OutOfBounds { min: i128, max: i128 },
+    /// A numeric argument that has to be non-zero, such as `NonZeroU32`, is zero.
+    Zero,
+    /// An error with a message given by a custom `Argument` implementation, see `CommandParser::custom_error`.
+    Custom(Cow<'static, str>),
+}
+
+impl ParseErrorKind {
+    /// Whether the kind says nothing about what was expected instead,
+    /// in which case the message includes the `ExpectedSet` attached to the error.
+    fn is_generic(&self) -> bool {
+        matches!(
+            self,
+            ParseErrorKind::UnexpectedEof
+                | ParseErrorKind::ExpectedEof
+                | ParseErrorKind::BadArgument
+                | ParseErrorKind::BadLiteral
+                | ParseErrorKind::ExpectedWhitespace
+        )
+    }
+
+    /// A stable, machine-readable code for this kind of error, e.g. `unknown_command`.
+    /// Unlike the displayed message, codes do not change between versions and can be used to tell errors apart.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseErrorKind::UnknownCommand => "unknown_command",
+            ParseErrorKind::UnexpectedEof => "unexpected_eof",
+            ParseErrorKind::ExpectedEof => "expected_eof",
+            ParseErrorKind::BadArgument => "bad_argument",
+            ParseErrorKind::BadLiteral => "bad_literal",
+            ParseErrorKind::ExpectedWhitespace => "expected_whitespace",
+            ParseErrorKind::ExpectedQuote => "expected_quote",
+            ParseErrorKind::UnterminatedString => "unterminated_string",
+            ParseErrorKind::UnexpectedQuote => "unexpected_quote",
+            ParseErrorKind::InvalidEncoding => "invalid_encoding",
+            ParseErrorKind::TooComplex => "too_complex",
+            ParseErrorKind::OutOfBounds { .. } => "out_of_bounds",
+            ParseErrorKind::Zero => "zero",
+            ParseErrorKind::Custom(_) => "custom",
+        }
+    }
 }
 
 /// A trait for parsing arguments from a command.
@@ -136,6 +813,16 @@ pub trait Argument {
     fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>>
     where
         Self: Sized;
+
+    /// Suggest values for this argument when completing a command, see `CommandSource::complete`.
+    /// The parser is positioned at the start of the argument.
+    /// Suggestions not matching what has been typed so far are filtered out afterwards.
+    fn suggestions(_parser: &CommandParser) -> Vec<String>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
 }
 
 impl Argument for () {
@@ -144,12 +831,138 @@ impl Argument for () {
     }
 }
 
+/// Parses whitespace-separated values until the end of the command, requiring at least one.
+impl<T: Argument> Argument for Vec<T> {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let mut values = vec![parser.argument::<T>()?];
+        loop {
+            let mut rest = parser.branch();
+            rest.read_while(char::is_whitespace);
+            if rest.remaining().is_empty() {
+                *parser = rest;
+                return Ok(values);
+            }
+            parser.spacing()?;
+            values.push(parser.argument::<T>()?);
+        }
+    }
+}
+
+macro_rules! argument_impl_tuple {
+    ($first:ident $(, $rest:ident)*) => {
+        /// Parses whitespace-separated elements in order, e.g. `3 4` for `(i32, i32)`.
+        impl<$first: Argument $(, $rest: Argument)*> Argument for ($first, $($rest,)*) {
+            fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+                Ok((
+                    parser.argument::<$first>()?,
+                    $({
+                        parser.spacing()?;
+                        parser.argument::<$rest>()?
+                    },)*
+                ))
+            }
+
+            /// Only the first element can be suggested, as the parser is positioned at the start of the argument.
+            fn suggestions(parser: &CommandParser) -> Vec<String> {
+                $first::suggestions(parser)
+            }
+        }
+    };
+}
+
+argument_impl_tuple!(A);
+argument_impl_tuple!(A, B);
+argument_impl_tuple!(A, B, C);
+argument_impl_tuple!(A, B, C, D);
+
+/// Parses one of the words given by `ParserConfig::true_words` and `ParserConfig::false_words`, e.g. `on` or `no`.
+impl Argument for bool {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        let word = parser.read_while(|c| !c.is_whitespace());
+        if word.is_empty() {
+            return Err(start.error(ParseErrorKind::UnexpectedEof));
+        }
+        let matches = |words: &[String]| words.iter().any(|w| w.eq_ignore_ascii_case(word));
+        if matches(&parser.config().true_words) {
+            Ok(true)
+        } else if matches(&parser.config().false_words) {
+            Ok(false)
+        } else {
+            Err(start.error(ParseErrorKind::BadArgument))
+        }
+    }
+
+    fn suggestions(parser: &CommandParser) -> Vec<String> {
+        let config = parser.config();
+        config
+            .true_words
+            .iter()
+            .chain(&config.false_words)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Parses a single character, which may be enclosed in single quotes, e.g. `' '` for a space.
+/// Within quotes, `\` escapes the following character.
+/// Tokens consisting of more than one character are rejected rather than split, including characters followed by combining marks.
+impl Argument for char {
+    fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+        let start = parser.branch();
+        if parser.remaining().starts_with('\'') {
+            parser.advance(1);
+            let mut chars = parser.remaining().chars();
+            let c = match chars.next() {
+                Some('\\') => chars.next(),
+                c => c,
+            }
+            .ok_or_else(|| parser.error(ParseErrorKind::UnexpectedEof))?;
+            let len = parser.remaining().len() - chars.as_str().len();
+            parser.advance(len);
+            if parser.remaining().starts_with('\'') {
+                parser.advance(1);
+                Ok(c)
+            } else {
+                Err(start.error(ParseErrorKind::BadArgument))
+            }
+        } else {
+            let token = parser.read_while(|c| !c.is_whitespace());
+            let mut chars = token.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                (None, _) => Err(start.error(ParseErrorKind::UnexpectedEof)),
+                _ => Err(start.error(ParseErrorKind::BadArgument)),
+            }
+        }
+    }
+}
+
+/// Parses a string enclosed in double quotes, in which `\` escapes the following character.
+/// Depending on the parser's `QuotingPolicy`, single words may also be given without quotes.
 impl Argument for String {
     fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>>
     where
         Self: Sized,
     {
-        parser.lit("\"")?;
+        let start = parser.branch();
+        if parser.lit("\"").is_err() {
+            return match parser.config().quoting {
+                QuotingPolicy::Always => Err(start.error(ParseErrorKind::ExpectedQuote)),
+                QuotingPolicy::Strict => {
+                    let word = parser.read_while(|c| !c.is_whitespace());
+                    if word.is_empty() {
+                        Err(start.error(ParseErrorKind::UnexpectedEof))
+                    } else if let Some(quote) = word.find('"') {
+                        let mut error = start.branch();
+                        error.advance(quote);
+                        Err(error.error(ParseErrorKind::UnexpectedQuote))
+                    } else {
+                        Ok(word.to_string())
+                    }
+                }
+            };
+        }
         let mut result = String::new();
         let mut escape = false;
         parser.read_while(|c| {
@@ -167,8 +980,10 @@ impl Argument for String {
                 true
             }
         });
-        parser.lit("\"")?;
-        Ok(result.to_string())
+        parser
+            .lit("\"")
+            .map_err(|_| start.error(ParseErrorKind::UnterminatedString))?;
+        Ok(result)
     }
 }
 
@@ -185,50 +1000,172 @@ macro_rules! cond {
     };
 }
 
-/// Implement the `Argument` trait for a list of integer types.
+/// The notation numeric arguments are parsed in.
+/// Set for individual arguments using the `radix` and `digit_separator` attributes of `define_command!`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// The base of integers, between 2 and 36. Floats can only be parsed in base 10.
+    pub radix: u32,
+    /// A character that may be used to group digits, e.g. `_` in `1_000_000`.
+    pub separator: Option<char>,
+}
+
+impl NumberFormat {
+    /// The format numeric arguments are parsed in unless specified otherwise: decimal, using `ParserConfig::digit_separator`.
+    pub fn from_config(config: &ParserConfig) -> Self {
+        Self {
+            separator: config.digit_separator,
+            ..Default::default()
+        }
+    }
+
+    /// Disable the digit separator if it is `separator`, e.g. for numbers in lists separated by it.
+    pub(crate) fn without_separator(self, separator: char) -> Self {
+        Self {
+            separator: self.separator.filter(|&c| c != separator),
+            ..self
+        }
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            radix: 10,
+            separator: None,
+        }
+    }
+}
+
+/// Numeric arguments that can be parsed in a specific `NumberFormat`.
+pub trait Number: Argument + Sized {
+    fn parse_with<'a>(
+        parser: &mut CommandParser<'a>,
+        format: NumberFormat,
+    ) -> Result<Self, ParseError<'a>>;
+}
+
+/// Read digits possibly grouped by the given separator, returning them with the separators removed.
+/// Separators may not appear at the start or end of the number.
+pub(crate) fn read_digits(
+    parser: &mut CommandParser,
+    mut digit: impl FnMut(char) -> bool,
+    separator: Option<char>,
+) -> Option<String> {
+    let text = parser.read_while(|c| Some(c) == separator || digit(c));
+    if text.is_empty()
+        || separator
+            .is_some_and(|separator| text.starts_with(separator) || text.ends_with(separator))
+    {
+        return None;
+    }
+    Some(text.chars().filter(|&c| Some(c) != separator).collect())
+}
+
+/// Skip a `0x`, `0o` or `0b` prefix and return the radix it denotes.
+/// Any prefix is accepted for decimal numbers, otherwise only the one matching `radix`,
+/// as e.g. `0b` consists of valid hexadecimal digits.
+fn prefixed_radix(parser: &mut CommandParser, radix: u32) -> u32 {
+    let prefixed = match parser.remaining().get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => return radix,
+    };
+    if radix == 10 || radix == prefixed {
+        parser.advance(2);
+        prefixed
+    } else {
+        radix
+    }
+}
+
+/// Implement the `Argument` and `Number` traits for a list of integer types.
 /// Uses a boolean parameter with the `cond!` macro to conditionally generate code for signed integers instead of having two separate macro definitons.
 macro_rules! argument_impl_int {
     ($signed: ident, $($t:ty),*) => {
         $(
-            impl Argument for $t {
-                fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
-                    let sign = cond! {
+            impl Number for $t {
+                fn parse_with<'a>(parser: &mut CommandParser<'a>, format: NumberFormat) -> Result<Self, ParseError<'a>> {
+                    let start = parser.branch();
+                    if !(2..=36).contains(&format.radix) {
+                        return Err(start.error(ParseErrorKind::BadArgument));
+                    }
+                    let negative = cond! {
                         if $signed {
-                            if parser.command[parser.offset..].starts_with('-') {
+                            if parser.remaining().starts_with('-') {
                                 parser.advance(1);
-                                -1
+                                true
                             } else {
-                                1
+                                false
                             }
                         } else {
-                            1
+                            false
                         }
                     };
-                    let num = parser.read_while(|c| c.is_ascii_digit() );
-                    if num.is_empty() {
-                        Err(parser.error(ParseErrorKind::BadArgument))
-                    } else {
-                        Ok(num.parse::<$t>().map_err(|_| parser.error(ParseErrorKind::BadArgument))? * sign)
-                    }
+                    let radix = prefixed_radix(parser, format.radix);
+                    let digits = read_digits(parser, |c| c.is_digit(radix), format.separator)
+                        .ok_or_else(|| start.error(ParseErrorKind::BadArgument))?;
+                    let digits = if negative { format!("-{}", digits) } else { digits };
+                    <$t>::from_str_radix(&digits, radix).map_err(|_| start.error(ParseErrorKind::BadArgument))
+                }
+            }
+
+            impl Argument for $t {
+                fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+                    Self::parse_with(parser, NumberFormat::from_config(parser.config()))
                 }
             }
         )*
     };
 }
 
-macro_rules! argument_impl_float {
-    ($($t:ty),*) => {
+/// Implement the `Argument` and `Number` traits for non-zero integer types using the implementations of the underlying integers.
+macro_rules! argument_impl_nonzero {
+    ($($t:ty => $int:ty),*) => {
         $(
+            impl Number for $t {
+                fn parse_with<'a>(parser: &mut CommandParser<'a>, format: NumberFormat) -> Result<Self, ParseError<'a>> {
+                    let start = parser.branch();
+                    let value = <$int>::parse_with(parser, format)?;
+                    <$t>::new(value).ok_or_else(|| start.error(ParseErrorKind::Zero))
+                }
+            }
+
             impl Argument for $t {
                 fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
-                    let sign = if parser.command[parser.offset..].starts_with('-') {
+                    Self::parse_with(parser, NumberFormat::from_config(parser.config()))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! argument_impl_float {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn parse_with<'a>(parser: &mut CommandParser<'a>, format: NumberFormat) -> Result<Self, ParseError<'a>> {
+                    let start = parser.branch();
+                    if format.radix != 10 {
+                        return Err(start.error(ParseErrorKind::BadArgument));
+                    }
+                    let sign = if parser.remaining().starts_with('-') {
                         parser.advance(1);
                         -1.0
                     } else {
                         1.0
                     };
+                    if parser.config().special_floats && parser.remaining().starts_with(char::is_alphabetic) {
+                        let value = match parser.read_while(char::is_alphabetic).to_lowercase().as_str() {
+                            "inf" | "infinity" => <$t>::INFINITY,
+                            "nan" => <$t>::NAN,
+                            _ => return Err(start.error(ParseErrorKind::BadArgument)),
+                        };
+                        return Ok(value * sign);
+                    }
                     let mut decimals = false;
-                    let num = parser.read_while(|c|  {
+                    let mut num = read_digits(parser, |c|  {
                         if c == '.' {
                             if decimals  {
                                 false
@@ -239,12 +1176,34 @@ macro_rules! argument_impl_float {
                         } else {
                             c.is_ascii_digit()
                         }
-                    });
-                    if num.is_empty() {
-                        Err(parser.error(ParseErrorKind::BadArgument))
-                    } else {
-                        Ok(num.parse::<$t>().map_err(|_| parser.error(ParseErrorKind::BadArgument))? * sign)
+                    }, format.separator)
+                    .ok_or_else(|| start.error(ParseErrorKind::BadArgument))?;
+                    // An exponent like `e-3`, which is left alone unless digits follow
+                    let mut exponent = parser.branch();
+                    if exponent.remaining().starts_with(['e', 'E']) {
+                        exponent.advance(1);
+                        let sign = match exponent.remaining().chars().next() {
+                            Some(sign @ ('+' | '-')) => {
+                                exponent.advance(1);
+                                Some(sign)
+                            }
+                            _ => None,
+                        };
+                        let digits = exponent.read_while(|c| c.is_ascii_digit());
+                        if !digits.is_empty() {
+                            num.push('e');
+                            num.extend(sign);
+                            num.push_str(digits);
+                            *parser = exponent;
+                        }
                     }
+                    Ok(num.parse::<$t>().map_err(|_| start.error(ParseErrorKind::BadArgument))? * sign)
+                }
+            }
+
+            impl Argument for $t {
+                fn parse<'a>(parser: &mut CommandParser<'a>) -> Result<Self, ParseError<'a>> {
+                    Self::parse_with(parser, NumberFormat::from_config(parser.config()))
                 }
             }
         )*
@@ -253,4 +1212,250 @@ macro_rules! argument_impl_float {
 
 argument_impl_int!(false, u8, u16, u32, u64, u128, usize);
 argument_impl_int!(true, i8, i16, i32, i64, i128, isize);
+argument_impl_nonzero!(
+    NonZeroU8 => u8, NonZeroU16 => u16, NonZeroU32 => u32, NonZeroU64 => u64, NonZeroU128 => u128, NonZeroUsize => usize,
+    NonZeroI8 => i8, NonZeroI16 => i16, NonZeroI32 => i32, NonZeroI64 => i64, NonZeroI128 => i128, NonZeroIsize => isize
+);
 argument_impl_float!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Bound::Included, rc::Rc};
+
+    use super::{CommandParser, Expected, ExpectedSet, ParseErrorKind, ParserConfig, TokenKind};
+    use crate::arguments::{
+        Base64, DiceRoll, Email, Hex, Interval, KvMap, List, Region, WeightedList,
+    };
+
+    fn base64(text: &str) -> Option<Vec<u8>> {
+        CommandParser::new(text)
+            .argument::<Base64<Vec<u8>, 4>>()
+            .ok()
+            .map(|Base64(bytes)| bytes)
+    }
+
+    #[test]
+    fn base64_padding_and_alphabets() {
+        assert_eq!(base64("aGk="), Some(b"hi".to_vec()));
+        assert_eq!(base64("aGk"), Some(b"hi".to_vec()));
+        assert_eq!(base64("aA=="), Some(b"h".to_vec()));
+        assert_eq!(base64("+/8"), Some(vec![0xfb, 0xff]));
+        assert_eq!(base64("-_8"), Some(vec![0xfb, 0xff]));
+        // Nothing but padding
+        assert_eq!(base64("=="), None);
+        // Padding where the length doesn't call for it, or too much of it
+        assert_eq!(base64("aGk=="), None);
+        assert_eq!(base64("aA="), None);
+        assert_eq!(base64("aA==="), None);
+        assert_eq!(base64("aGlh="), None);
+        // Non-zero bits after the last full byte
+        assert_eq!(base64("aB=="), None);
+        assert_eq!(base64("aGl"), None);
+        // Mixed alphabets
+        assert_eq!(base64("+_8"), None);
+    }
+
+    #[test]
+    fn oversized_encoded_tokens() {
+        assert_eq!(base64("aGloaQ"), Some(b"hihi".to_vec()));
+        assert_eq!(base64("aGloaWk"), None);
+
+        let hex = |text| CommandParser::new(text).argument::<Hex<Vec<u8>, 2>>();
+        assert_eq!(hex("0xbeef").unwrap().0, [0xbe, 0xef]);
+        assert!(hex("beefef").is_err());
+        let long = "0".repeat(1 << 20);
+        let error = hex(&long).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::BadArgument);
+        assert_eq!(error.offset, 0);
+    }
+
+    #[test]
+    fn interval_bounds_use_the_parser_config() {
+        let config = Rc::new(ParserConfig {
+            digit_separator: Some('_'),
+            ..Default::default()
+        });
+        let mut parser = CommandParser::with_config("range 1_000..=2_000", config);
+        parser.advance(6);
+        let interval = parser.argument::<Interval<u32>>().unwrap();
+        assert_eq!(interval.bounds(), (Included(1000), Included(2000)));
+
+        let mut parser = CommandParser::new("range 5..x");
+        parser.advance(6);
+        let error = parser.argument::<Interval<u32>>().unwrap_err();
+        assert_eq!(error.offset, 9);
+    }
+
+    #[test]
+    fn literals_charge_the_budget_per_character() {
+        let config = ParserConfig {
+            budget: Some(3),
+            ..Default::default()
+        };
+        let mut parser = CommandParser::with_config("日本 語", Rc::new(config));
+        parser.lit("日本").unwrap();
+        assert!(!parser.budget_exceeded());
+        parser.spacing().unwrap();
+        assert_eq!(
+            parser.lit("語x").unwrap_err().kind,
+            ParseErrorKind::TooComplex
+        );
+    }
+
+    #[test]
+    fn tokens_with_non_ascii_words() {
+        let parser = CommandParser::new("say élan über 日本 ß");
+        let tokens: Vec<_> = parser
+            .tokens()
+            .map(|token| (token.kind, token.text))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (TokenKind::Word, "say"),
+                (TokenKind::Word, "élan"),
+                (TokenKind::Word, "über"),
+                (TokenKind::Word, "日本"),
+                (TokenKind::Word, "ß"),
+            ]
+        );
+    }
+
+    #[test]
+    fn key_value_pairs_without_a_value() {
+        let mut parser = CommandParser::new("opts mode= label=x");
+        parser.advance(5);
+        let error = parser.argument::<KvMap>().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::BadArgument);
+        assert_eq!(error.offset, 10);
+
+        let mut parser = CommandParser::new(r#"opts mode="" label=x"#);
+        parser.advance(5);
+        let map = parser.argument::<KvMap>().unwrap();
+        assert_eq!((map.get("mode"), map.get("label")), (Some(""), Some("x")));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn quoted_regex_keeps_backslashes() {
+        let mut parser = CommandParser::new(r#"grep "\d+ \"x\"""#);
+        parser.advance(5);
+        let regex = parser.argument::<regex::Regex>().unwrap();
+        assert_eq!(regex.as_str(), r#"\d+ "x""#);
+        assert!(regex.is_match(r#"42 "x""#));
+    }
+
+    #[test]
+    fn dice_counts_above_the_limit() {
+        let mut parser = CommandParser::new("roll 1000d6");
+        parser.advance(5);
+        assert_eq!(parser.argument::<DiceRoll>().unwrap().count, 1000);
+
+        let mut parser = CommandParser::new("roll 4000000000d6");
+        parser.advance(5);
+        let error = parser.argument::<DiceRoll>().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::BadArgument);
+        assert_eq!(error.offset, 5);
+    }
+
+    #[test]
+    fn expected_offsets_outside_the_command() {
+        let mut expected = ExpectedSet::default();
+        expected.insert(20, Expected::End);
+        let error = CommandParser::new("say é")
+            .error(ParseErrorKind::BadArgument)
+            .with_expected(expected);
+        assert_eq!(
+            error.to_string(),
+            "Bad argument: at column 6 expected end of input"
+        );
+
+        let mut expected = ExpectedSet::default();
+        expected.insert(5, Expected::End);
+        let error = CommandParser::new("say é")
+            .error(ParseErrorKind::BadArgument)
+            .with_expected(expected);
+        assert_eq!(
+            error.to_string(),
+            "Bad argument: at column 5 expected end of input"
+        );
+    }
+
+    #[test]
+    fn list_element_errors_span_the_full_command() {
+        let mut parser = CommandParser::new("pick 1,x 5 zzz");
+        parser.advance(5);
+        let error = parser.argument::<List<u32>>().unwrap_err();
+        let mut expected = ExpectedSet::default();
+        expected.insert(11, Expected::End);
+        let message = error.with_expected(expected).to_string();
+        assert_eq!(message, "Bad argument: at column 12 expected end of input");
+    }
+
+    #[test]
+    fn long_email_local_parts() {
+        let local = "é".repeat(65);
+        let command = format!("mail {}@example.com", local);
+        let mut parser = CommandParser::new(&command);
+        parser.advance(5);
+        let error = parser.argument::<Email>().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::BadArgument);
+        assert_eq!(error.offset, 5 + "é".len() * 64);
+
+        let local = "a".repeat(64);
+        assert!(Email::new(&format!("{}@example.com", local)).is_some());
+        assert!(Email::new(&format!("{}a@example.com", local)).is_none());
+    }
+
+    #[test]
+    fn comma_separated_numbers_with_comma_digit_separators() {
+        let config = Rc::new(ParserConfig {
+            digit_separator: Some(','),
+            ..Default::default()
+        });
+        let mut parser = CommandParser::with_config("1,2,3 4,5,6", config.clone());
+        let region = parser.argument::<Region>().unwrap();
+        assert_eq!((region.min, region.max), ([1, 2, 3], [4, 5, 6]));
+
+        let mut parser = CommandParser::with_config("from 1,000 0 0 to 2,000 1 1", config.clone());
+        let region = parser.argument::<Region>().unwrap();
+        assert_eq!((region.min, region.max), ([1000, 0, 0], [2000, 1, 1]));
+
+        let mut parser = CommandParser::with_config("zombie:3,skeleton:1", config.clone());
+        assert_eq!(
+            parser.argument::<WeightedList>().unwrap().0,
+            [("zombie".to_string(), 3), ("skeleton".to_string(), 1)]
+        );
+        let mut parser = CommandParser::with_config("zombie:1,000", config);
+        assert!(parser.argument::<WeightedList>().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "different command")]
+    fn rollback_to_checkpoint_of_other_command() {
+        let mut longer = CommandParser::new("say hello world");
+        longer.advance(10);
+        let mut parser = CommandParser::new("say hi");
+        parser.rollback(longer.checkpoint());
+    }
+
+    #[test]
+    #[should_panic(expected = "different command")]
+    fn rollback_to_checkpoint_of_command_with_same_length() {
+        let first = String::from("say hi");
+        let second = String::from("say yo");
+        let mut other = CommandParser::new(&first);
+        other.advance(4);
+        let mut parser = CommandParser::new(&second);
+        parser.rollback(other.checkpoint());
+    }
+
+    #[test]
+    fn rollback_to_checkpoint_of_branch() {
+        let mut parser = CommandParser::new("say hi");
+        let mut branch = parser.branch();
+        branch.advance(4);
+        parser.rollback(branch.checkpoint());
+        assert_eq!(parser.remaining(), "hi");
+    }
+}
@@ -0,0 +1,58 @@
+//! Conversion of command outcomes into representations for different frontends, such as terminals or chat.
+
+use crate::{CommandError, CommandResult};
+
+/// Converts the outcome of a command into a frontend-specific representation, e.g. colored text or JSON.
+pub trait RenderOutcome {
+    type Output;
+
+    /// Render the status code returned by a successful command.
+    fn render_success(&self, status: i32) -> Self::Output;
+
+    /// Render an error that occurred while parsing or executing a command.
+    fn render_error(&self, error: &CommandError) -> Self::Output;
+
+    /// Render the result of a command.
+    fn render(&self, result: &CommandResult) -> Self::Output {
+        match result {
+            Ok(status) => self.render_success(*status),
+            Err(error) => self.render_error(error),
+        }
+    }
+}
+
+/// Renders outcomes as plain text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainText;
+
+impl RenderOutcome for PlainText {
+    type Output = String;
+
+    fn render_success(&self, status: i32) -> String {
+        if status == 0 {
+            "Done".to_string()
+        } else {
+            format!("Done (status {})", status)
+        }
+    }
+
+    fn render_error(&self, error: &CommandError) -> String {
+        format!("Error: {}", error)
+    }
+}
+
+/// Renders outcomes as text colored using ANSI escape codes, green for successes and red for errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ansi;
+
+impl RenderOutcome for Ansi {
+    type Output = String;
+
+    fn render_success(&self, status: i32) -> String {
+        format!("\x1b[32m{}\x1b[0m", PlainText.render_success(status))
+    }
+
+    fn render_error(&self, error: &CommandError) -> String {
+        format!("\x1b[31m{}\x1b[0m", PlainText.render_error(error))
+    }
+}
@@ -0,0 +1,432 @@
+//! Snapshots of the registered commands for networked clients, see `CommandSource::schema`.
+//!
+//! Clients can cache a snapshot along with its checksum and only fetch it again once the checksum changes,
+//! or apply a `SchemaDiff` containing only the commands that changed.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{dynamic::ArgumentRegistry, CommandUsage, SyntaxNode};
+
+/// A single part of a command variant's syntax.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NodeSchema {
+    Literal(String),
+    Argument {
+        name: String,
+        /// The localization key of the argument, see `ArgumentUsage::key`.
+        key: String,
+        /// The name of the argument's type, e.g. `u32`.
+        ty: String,
+        /// The kind the type resolves to in the source's `ArgumentRegistry`, see `ArgumentRegistry::kind_of`,
+        /// or `None` if it isn't registered.
+        kind: Option<String>,
+        /// The help text of the argument, if any.
+        help: Option<String>,
+        optional: bool,
+    },
+}
+
+/// The syntax of a command variant along with its localization key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VariantSchema {
+    /// The localization key of the variant, see `VariantUsage::key`.
+    pub key: String,
+    pub nodes: Vec<NodeSchema>,
+}
+
+/// The syntax of a registered command along with its aliases.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommandSchema {
+    pub name: String,
+    /// The localization key of the command, see `CommandUsage::key`.
+    pub key: String,
+    pub aliases: Vec<String>,
+    pub variants: Vec<VariantSchema>,
+}
+
+impl CommandSchema {
+    /// Describe the syntax of a command, resolving the types of its arguments to kinds through `registry`.
+    pub fn new(usage: &CommandUsage, aliases: &[&str], registry: &ArgumentRegistry) -> Self {
+        let variants = usage
+            .variants
+            .iter()
+            .map(|variant| VariantSchema {
+                key: variant.key.to_string(),
+                nodes: variant
+                    .syntax
+                    .iter()
+                    .map(|node| match node {
+                        SyntaxNode::Literal(literal) => NodeSchema::Literal(literal.to_string()),
+                        SyntaxNode::Argument(argument) => NodeSchema::Argument {
+                            name: argument.name.to_string(),
+                            key: argument.key.to_string(),
+                            ty: argument.ty.to_string(),
+                            kind: registry.kind_of(argument.ty).map(str::to_string),
+                            help: argument.help.map(str::to_string),
+                            optional: argument.optional,
+                        },
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self {
+            name: usage.name.to_string(),
+            key: usage.key.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            variants,
+        }
+    }
+
+    /// Write the command as a single line of the compact format, without the line break.
+    /// Fields are separated by tabs: the name, the key, the aliases separated by spaces, then one field per variant.
+    /// Variants consist of their key followed by their nodes, separated by spaces. Nodes are written as `l:literal`,
+    /// or `a:name:type:key:kind` for arguments, with an empty kind if the type isn't registered,
+    /// starting with `o` instead for optional ones and followed by `:help` if they have a help text.
+    fn write_compact(&self, out: &mut String) {
+        out.push_str(&escape(&self.name));
+        out.push('\t');
+        out.push_str(&escape(&self.key));
+        out.push('\t');
+        let aliases = self.aliases.iter().map(|alias| escape(alias));
+        out.push_str(&aliases.collect::<Vec<_>>().join(" "));
+        for variant in &self.variants {
+            out.push('\t');
+            out.push_str(&escape(&variant.key));
+            for node in &variant.nodes {
+                out.push(' ');
+                match node {
+                    NodeSchema::Literal(literal) => {
+                        out.push_str(&format!("l:{}", escape(literal)));
+                    }
+                    NodeSchema::Argument {
+                        name,
+                        key,
+                        ty,
+                        kind,
+                        help,
+                        optional,
+                    } => {
+                        out.push_str(&format!(
+                            "{}:{}:{}:{}:{}",
+                            if *optional { 'o' } else { 'a' },
+                            escape(name),
+                            escape(ty),
+                            escape(key),
+                            kind.as_deref().map(escape).unwrap_or_default()
+                        ));
+                        if let Some(help) = help {
+                            out.push(':');
+                            out.push_str(&escape(help));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_compact(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let name = unescape(fields.next()?)?;
+        let key = unescape(fields.next()?)?;
+        let aliases = fields
+            .next()?
+            .split(' ')
+            .filter(|alias| !alias.is_empty())
+            .map(unescape)
+            .collect::<Option<_>>()?;
+        let variants = fields
+            .map(|variant| {
+                let mut items = variant.split(' ');
+                Some(VariantSchema {
+                    key: unescape(items.next()?)?,
+                    nodes: items
+                        .filter(|node| !node.is_empty())
+                        .map(read_node)
+                        .collect::<Option<_>>()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Self {
+            name,
+            key,
+            aliases,
+            variants,
+        })
+    }
+}
+
+fn read_node(node: &str) -> Option<NodeSchema> {
+    let mut parts = node.split(':');
+    let node = match (parts.next()?, parts.next()?, parts.next(), parts.next()) {
+        ("l", literal, None, None) => NodeSchema::Literal(unescape(literal)?),
+        (node @ ("a" | "o"), name, Some(ty), Some(key)) => NodeSchema::Argument {
+            name: unescape(name)?,
+            key: unescape(key)?,
+            ty: unescape(ty)?,
+            kind: match parts.next()? {
+                "" => None,
+                kind => Some(unescape(kind)?),
+            },
+            help: match parts.next() {
+                Some(help) => Some(unescape(help)?),
+                None => None,
+            },
+            optional: node == "o",
+        },
+        _ => return None,
+    };
+    parts.next().is_none().then_some(node)
+}
+
+/// Escape the characters used as separators in the compact format.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ' ' => escaped.push_str("\\s"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            ':' => escaped.push_str("\\c"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                's' => ' ',
+                't' => '\t',
+                'n' => '\n',
+                'r' => '\r',
+                'c' => ':',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(unescaped)
+}
+
+/// A 64-bit FNV-1a hash, which unlike the hashers of the standard library is stable across versions and platforms.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The header line starting a schema in the compact format.
+const SCHEMA_HEADER: &str = "oberst-schema 2";
+/// The header line starting a diff in the compact format.
+const DIFF_HEADER: &str = "oberst-schema-diff 2";
+
+/// The compact format could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaError {
+    /// The line that could not be read, starting at 1.
+    pub line: usize,
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid schema in line {}", self.line)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Check the header of the compact format and return the checksum it contains, or the two checksums of a diff.
+fn read_header<'s>(lines: &mut impl Iterator<Item = &'s str>, header: &str) -> Option<Vec<u64>> {
+    let rest = lines.next()?.strip_prefix(header)?;
+    rest.split_whitespace()
+        .map(|checksum| u64::from_str_radix(checksum, 16).ok())
+        .collect()
+}
+
+/// A snapshot of all commands registered to a source, ordered by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Schema {
+    commands: BTreeMap<String, CommandSchema>,
+}
+
+impl Schema {
+    pub fn new(commands: impl IntoIterator<Item = CommandSchema>) -> Self {
+        Self {
+            commands: commands
+                .into_iter()
+                .map(|command| (command.name.clone(), command))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CommandSchema> {
+        self.commands.get(name)
+    }
+
+    /// The commands in the snapshot, ordered by name.
+    pub fn commands(&self) -> impl Iterator<Item = &CommandSchema> {
+        self.commands.values()
+    }
+
+    fn write_commands(&self) -> String {
+        let mut out = String::new();
+        for command in self.commands.values() {
+            command.write_compact(&mut out);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// A checksum of the snapshot which only changes if the commands do.
+    /// Clients can compare it to the checksum of their cached snapshot to decide whether to fetch a new one.
+    pub fn checksum(&self) -> u64 {
+        fnv1a(self.write_commands().as_bytes())
+    }
+
+    /// Write the snapshot in a compact, line-based text format starting with its checksum,
+    /// which can be read using `deserialize_compact`.
+    pub fn serialize_compact(&self) -> String {
+        let commands = self.write_commands();
+        format!(
+            "{} {:016x}\n{}",
+            SCHEMA_HEADER,
+            fnv1a(commands.as_bytes()),
+            commands
+        )
+    }
+
+    /// Read a snapshot written by `serialize_compact`, checking that its contents match its checksum.
+    pub fn deserialize_compact(text: &str) -> Result<Self, SchemaError> {
+        let mut lines = text.lines();
+        let checksum = match read_header(&mut lines, SCHEMA_HEADER).as_deref() {
+            Some(&[checksum]) => checksum,
+            _ => return Err(SchemaError { line: 1 }),
+        };
+        let commands = lines
+            .enumerate()
+            .map(|(i, line)| CommandSchema::read_compact(line).ok_or(SchemaError { line: i + 2 }))
+            .collect::<Result<Vec<_>, _>>()?;
+        let schema = Schema::new(commands);
+        if schema.checksum() == checksum {
+            Ok(schema)
+        } else {
+            Err(SchemaError { line: 1 })
+        }
+    }
+
+    /// Describe how to turn `previous` into this snapshot, listing only the commands that were added, changed or removed.
+    pub fn diff(&self, previous: &Schema) -> SchemaDiff {
+        SchemaDiff {
+            from: previous.checksum(),
+            to: self.checksum(),
+            changed: self
+                .commands
+                .values()
+                .filter(|command| previous.get(&command.name) != Some(command))
+                .cloned()
+                .collect(),
+            removed: previous
+                .commands
+                .keys()
+                .filter(|name| !self.commands.contains_key(*name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Update the snapshot using a diff created by `diff`.
+    /// Fails without changing the snapshot if the diff was created for a different snapshot.
+    pub fn apply(&mut self, diff: &SchemaDiff) -> Result<(), SchemaError> {
+        if self.checksum() != diff.from {
+            return Err(SchemaError { line: 1 });
+        }
+        let mut updated = self.clone();
+        for name in &diff.removed {
+            updated.commands.remove(name);
+        }
+        for command in &diff.changed {
+            updated
+                .commands
+                .insert(command.name.clone(), command.clone());
+        }
+        if updated.checksum() != diff.to {
+            return Err(SchemaError { line: 1 });
+        }
+        *self = updated;
+        Ok(())
+    }
+}
+
+/// The changes between two snapshots, see `Schema::diff`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SchemaDiff {
+    /// The checksum of the snapshot the diff applies to.
+    pub from: u64,
+    /// The checksum of the snapshot after applying the diff.
+    pub to: u64,
+    /// Commands that were added or changed.
+    pub changed: Vec<CommandSchema>,
+    /// The names of commands that were removed.
+    pub removed: Vec<String>,
+}
+
+impl SchemaDiff {
+    /// Whether the snapshots are the same.
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// Write the diff in the compact format: a header with both checksums,
+    /// followed by `+` and a command for each added or changed command and `-` and a name for each removed one.
+    pub fn serialize_compact(&self) -> String {
+        let mut out = format!("{} {:016x} {:016x}\n", DIFF_HEADER, self.from, self.to);
+        for command in &self.changed {
+            out.push('+');
+            command.write_compact(&mut out);
+            out.push('\n');
+        }
+        for name in &self.removed {
+            out.push('-');
+            out.push_str(&escape(name));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Read a diff written by `serialize_compact`.
+    pub fn deserialize_compact(text: &str) -> Result<Self, SchemaError> {
+        let mut lines = text.lines();
+        let (from, to) = match read_header(&mut lines, DIFF_HEADER).as_deref() {
+            Some(&[from, to]) => (from, to),
+            _ => return Err(SchemaError { line: 1 }),
+        };
+        let mut diff = SchemaDiff {
+            from,
+            to,
+            changed: vec![],
+            removed: vec![],
+        };
+        for (i, line) in lines.enumerate() {
+            let error = SchemaError { line: i + 2 };
+            if let Some(command) = line.strip_prefix('+') {
+                diff.changed
+                    .push(CommandSchema::read_compact(command).ok_or(error)?);
+            } else if let Some(name) = line.strip_prefix('-') {
+                diff.removed.push(unescape(name).ok_or(error)?);
+            } else {
+                return Err(error);
+            }
+        }
+        Ok(diff)
+    }
+}
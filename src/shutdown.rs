@@ -0,0 +1,128 @@
+//! Graceful shutdown of asynchronous dispatch, see `CommandSource::shutdown`.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+/// The outcome of `CommandSource::shutdown`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// The commands that were still running when the timeout elapsed, which fail with `CommandError::Cancelled`.
+    pub cancelled: Vec<String>,
+}
+
+impl ShutdownReport {
+    /// Whether all commands completed before the timeout elapsed.
+    pub fn is_clean(&self) -> bool {
+        self.cancelled.is_empty()
+    }
+}
+
+/// Keeps track of the commands currently running through `CommandSource::dispatch_async`.
+#[derive(Default)]
+pub(crate) struct InFlight {
+    state: RefCell<State>,
+}
+
+#[derive(Default)]
+struct State {
+    closed: bool,
+    cancelled: bool,
+    next_id: u64,
+    running: HashMap<u64, Running>,
+    /// Woken once the last command completes.
+    drained: Option<Waker>,
+}
+
+struct Running {
+    command: String,
+    waker: Option<Waker>,
+}
+
+/// Marks a command as running until dropped.
+pub(crate) struct Ticket<'a> {
+    in_flight: &'a InFlight,
+    id: u64,
+}
+
+impl InFlight {
+    /// Register a running command, unless shutting down.
+    pub(crate) fn start(&self, command: &str) -> Option<Ticket<'_>> {
+        let mut state = self.state.borrow_mut();
+        if state.closed {
+            return None;
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.running.insert(
+            id,
+            Running {
+                command: command.to_string(),
+                waker: None,
+            },
+        );
+        Some(Ticket {
+            in_flight: self,
+            id,
+        })
+    }
+
+    /// Stop accepting commands and wait for the running ones until `timeout` completes, then cancel the rest.
+    pub(crate) async fn shutdown(&self, timeout: impl Future<Output = ()>) -> ShutdownReport {
+        self.state.borrow_mut().closed = true;
+        let mut timeout = pin!(timeout);
+        std::future::poll_fn(|cx| {
+            let mut state = self.state.borrow_mut();
+            if state.running.is_empty() {
+                return Poll::Ready(ShutdownReport::default());
+            }
+            state.drained = Some(cx.waker().clone());
+            drop(state);
+            if timeout.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+
+            let mut state = self.state.borrow_mut();
+            state.cancelled = true;
+            let mut cancelled = vec![];
+            for running in state.running.values_mut() {
+                cancelled.push(running.command.clone());
+                if let Some(waker) = running.waker.take() {
+                    waker.wake();
+                }
+            }
+            Poll::Ready(ShutdownReport { cancelled })
+        })
+        .await
+    }
+}
+
+impl Ticket<'_> {
+    /// Whether the command has been cancelled, remembering to wake its task once it is.
+    pub(crate) fn cancelled(&self, cx: &Context) -> bool {
+        let mut state = self.in_flight.state.borrow_mut();
+        if state.cancelled {
+            return true;
+        }
+        if let Some(running) = state.running.get_mut(&self.id) {
+            running.waker = Some(cx.waker().clone());
+        }
+        false
+    }
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        let mut state = self.in_flight.state.borrow_mut();
+        state.running.remove(&self.id);
+        if state.running.is_empty() {
+            if let Some(waker) = state.drained.take() {
+                waker.wake();
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::{cell::RefCell, rc::Rc};
+
+use oberst::{arguments::Word, define_command, register_command, CommandError, CommandSource};
+
+/// The part of the application a reusable command library works with.
+#[derive(Default)]
+struct World {
+    entities: Vec<String>,
+}
+
+#[derive(Default)]
+struct App {
+    world: World,
+    ticks: u32,
+}
+
+define_command! {spawn (World) {
+    fn spawn(world: &mut World, name: Word) {
+        world.entities.push(name.to_string());
+    }
+}}
+
+define_command! {count (World) {
+    fn count(world: &World) {
+        oberst::write_output(&world.entities.len().to_string());
+    }
+}}
+
+define_command! {tick (App) {
+    fn tick(app: &mut App) {
+        app.ticks += 1;
+    }
+}}
+
+#[test]
+fn adapted_commands_run_against_the_projected_context() {
+    let app = Rc::new(RefCell::new(App::default()));
+    let context = app.clone();
+    let source =
+        CommandSource::with_context_accessor(move || Some(context.clone()), Default::default());
+    register_command!(source, tick);
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let written = output.clone();
+    source.set_output(move |text| written.borrow_mut().push(text.to_string()));
+    let library = source.adapt(|app: &App| &app.world, |app: &mut App| &mut app.world);
+    register_command!(library, spawn);
+    register_command!(library, count);
+
+    library.dispatch_mut("spawn zombie").unwrap();
+    library.dispatch_mut("spawn creeper").unwrap();
+    source.dispatch_mut("tick").unwrap();
+    library.dispatch("count").unwrap();
+    assert_eq!(app.borrow().world.entities, ["zombie", "creeper"]);
+    assert_eq!(app.borrow().ticks, 1);
+    assert_eq!(output.take(), ["2"]);
+    assert!(source.dispatch("count").is_err());
+
+    let borrowed = app.borrow_mut();
+    assert!(matches!(
+        library.dispatch("count"),
+        Err(CommandError::ContextBorrowed)
+    ));
+    drop(borrowed);
+    drop(source);
+    assert!(library.dispatch("count").is_ok());
+}
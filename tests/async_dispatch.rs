@@ -0,0 +1,122 @@
+#![cfg(feature = "async")]
+
+use std::{
+    cell::Cell,
+    future::{poll_fn, Future},
+    pin::pin,
+    rc::Rc,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+
+use oberst::{define_command, register_command, CommandError, CommandSource};
+
+/// A waker that does nothing, as the tests poll their futures by hand instead of using an executor.
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Poll `future` once.
+fn poll<F: Future>(future: std::pin::Pin<&mut F>) -> Poll<F::Output> {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    future.poll(&mut Context::from_waker(&waker))
+}
+
+/// A future that completes once the flag is set.
+fn wait_for(flag: &Cell<bool>) -> impl Future<Output = ()> + '_ {
+    poll_fn(move |_| {
+        if flag.get() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+}
+
+/// The context of the commands, whose flag `wait` waits for.
+struct Gate {
+    open: Rc<Cell<bool>>,
+}
+
+define_command! {wait (Gate) {
+    async fn wait(gate: &Gate) {
+        wait_for(&gate.open).await;
+    }
+}}
+
+define_command! {ping (Gate) {
+    fn ping(_gate: &Gate) {}
+}}
+
+/// Create a source with the commands registered, returning it along with the flag `wait` waits for.
+fn source() -> (CommandSource<Gate>, Rc<Cell<bool>>) {
+    let open = Rc::new(Cell::new(false));
+    let source = CommandSource::new(Gate { open: open.clone() });
+    register_command!(source, wait);
+    register_command!(source, ping);
+    (source, open)
+}
+
+#[test]
+fn async_commands_run_until_their_future_completes() {
+    let (source, open) = source();
+    assert!(matches!(
+        source.dispatch("wait"),
+        Err(CommandError::RequiresAsync)
+    ));
+
+    let mut waiting = pin!(source.dispatch_async("wait"));
+    assert!(poll(waiting.as_mut()).is_pending());
+    assert!(poll(waiting.as_mut()).is_pending());
+    open.set(true);
+    assert!(matches!(poll(waiting.as_mut()), Poll::Ready(Ok(0))));
+
+    let mut ping = pin!(source.dispatch_async("ping"));
+    assert!(matches!(poll(ping.as_mut()), Poll::Ready(Ok(0))));
+}
+
+#[test]
+fn shutdown_waits_for_running_commands() {
+    let (source, open) = source();
+    let mut waiting = pin!(source.dispatch_async("wait"));
+    assert!(poll(waiting.as_mut()).is_pending());
+
+    let timed_out = Cell::new(false);
+    let mut shutdown = pin!(source.shutdown(wait_for(&timed_out)));
+    assert!(poll(shutdown.as_mut()).is_pending());
+    let mut rejected = pin!(source.dispatch_async("ping"));
+    assert!(matches!(
+        poll(rejected.as_mut()),
+        Poll::Ready(Err(CommandError::ShuttingDown))
+    ));
+
+    open.set(true);
+    assert!(matches!(poll(waiting.as_mut()), Poll::Ready(Ok(0))));
+    let Poll::Ready(report) = poll(shutdown.as_mut()) else {
+        panic!("the shutdown should be complete once no command is running");
+    };
+    assert!(report.is_clean());
+}
+
+#[test]
+fn shutdown_cancels_commands_still_running_after_the_timeout() {
+    let (source, _open) = source();
+    let mut waiting = pin!(source.dispatch_async("wait"));
+    assert!(poll(waiting.as_mut()).is_pending());
+
+    let timed_out = Cell::new(false);
+    let mut shutdown = pin!(source.shutdown(wait_for(&timed_out)));
+    assert!(poll(shutdown.as_mut()).is_pending());
+    timed_out.set(true);
+    let Poll::Ready(report) = poll(shutdown.as_mut()) else {
+        panic!("the shutdown should be complete once the timeout elapsed");
+    };
+    assert!(!report.is_clean());
+    assert_eq!(report.cancelled, ["wait"]);
+    assert!(matches!(
+        poll(waiting.as_mut()),
+        Poll::Ready(Err(CommandError::Cancelled))
+    ));
+}
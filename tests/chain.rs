@@ -0,0 +1,47 @@
+use std::{cell::RefCell, rc::Rc};
+
+use oberst::{
+    chain::{self, Separator},
+    define_command,
+    parser::ParserConfig,
+    register_command, write_output, CommandSource,
+};
+
+type Output = Rc<RefCell<Vec<String>>>;
+
+define_command! {list (()) {
+    fn list(_context: &()) {
+        write_output("alex");
+        write_output("sam");
+    }
+}}
+
+define_command! {count (()) {
+    fn count(_context: &(), lines: String) {
+        write_output(&lines.lines().count().to_string());
+    }
+}}
+
+#[test]
+fn pipes_pass_the_output_on() {
+    let mut chain_separators = chain::default_separators();
+    chain_separators.push(("|".to_string(), Separator::Pipe));
+    let config = ParserConfig {
+        chain_separators,
+        ..Default::default()
+    };
+    let source = CommandSource::builder(()).config(config).build();
+    register_command!(source, list);
+    register_command!(source, count);
+    let output = Output::default();
+    let sink = output.clone();
+    source.set_output(move |text| sink.borrow_mut().push(text.to_string()));
+
+    source.dispatch_chain("list | count").unwrap();
+    assert_eq!(output.take(), ["2"]);
+    source.dispatch_chain("list; count \"\"").unwrap();
+    assert_eq!(output.take(), ["alex", "sam", "0"]);
+    // Only pipes standing alone separate commands
+    assert!(source.dispatch_chain("list|count").is_err());
+    assert!(output.take().is_empty());
+}
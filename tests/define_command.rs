@@ -0,0 +1,208 @@
+use std::{cell::RefCell, rc::Rc};
+
+use oberst::{
+    arguments::{GreedyString, Word},
+    define_command,
+    parser::{CommandParser, Expected},
+    register_command, CommandError, CommandResult, CommandSource, Execute,
+};
+
+/// Records the arguments each command was called with.
+#[derive(Default)]
+struct Calls(RefCell<Vec<String>>);
+
+impl Calls {
+    fn record(&self, call: String) {
+        self.0.borrow_mut().push(call);
+    }
+}
+
+fn words(words: &[Word]) -> Vec<&str> {
+    words.iter().map(|word| &**word).collect()
+}
+
+define_command! {give (Rc<Calls>) {
+    fn give(calls: &Rc<Calls>, #[greedy] items: Vec<Word>, target: Word) {
+        calls.record(format!("{:?} to {}", words(&items), &*target));
+    }
+}}
+
+define_command! {note (Rc<Calls>) {
+    #[args = "lazy <words> until <rest>"]
+    fn lazy(calls: &Rc<Calls>, #[lazy] words: Vec<Word>, rest: GreedyString) {
+        calls.record(format!("{:?} until {}", super::words(&words), rest));
+    }
+
+    #[args = "greedy <words> until <rest>"]
+    fn greedy(calls: &Rc<Calls>, #[greedy] words: Vec<Word>, rest: GreedyString) {
+        calls.record(format!("{:?} until {}", super::words(&words), rest));
+    }
+}}
+
+define_command! {#[alias("t", "tp2", "teleport_to")] tp (Rc<Calls>) {
+    fn tp(calls: &Rc<Calls>, x: i32, y: Option<i32>, z: Option<i32>) {
+        calls.record(format!("{} {:?} {:?}", x, y, z));
+    }
+}}
+
+define_command! {say (Rc<Calls>) {
+    fn say(calls: &Rc<Calls>, #[rename = "channel"] to: Word, message: GreedyString) {
+        calls.record(format!("{}: {}", &*to, message));
+    }
+}}
+
+define_command! {take (Rc<Calls>) {
+    fn take(calls: &Rc<Calls>, count: u32) {
+        calls.record(count.to_string());
+    }
+
+    #[args = "all"]
+    fn all(calls: &Rc<Calls>) {
+        calls.record("all".to_string());
+    }
+}}
+
+define_command! {fail (Rc<Calls>) {
+    fn fail(_calls: &Rc<Calls>, reason: GreedyString) -> CommandResult<'static> {
+        Err(CommandError::dispatch(reason.to_string()))
+    }
+}}
+
+/// Create a source with all commands registered, returning it along with its context.
+fn source() -> (CommandSource<Rc<Calls>>, Rc<Calls>) {
+    let calls = Rc::new(Calls::default());
+    let source = CommandSource::new(calls.clone());
+    register_command!(source, give);
+    register_command!(source, note);
+    register_command!(source, tp);
+    register_command!(source, say);
+    register_command!(source, take);
+    register_command!(source, fail);
+    (source, calls)
+}
+
+/// Dispatch `command` and return the calls it made, or the error message if it failed.
+fn dispatch(command: &str) -> Result<Vec<String>, String> {
+    let (source, calls) = source();
+    source
+        .dispatch(command)
+        .map_err(|error| error.to_string())?;
+    let recorded = calls.0.take();
+    Ok(recorded)
+}
+
+#[test]
+fn greedy_vec_followed_by_an_argument() {
+    assert_eq!(
+        dispatch("give apple pear alex").unwrap(),
+        [r#"["apple", "pear"] to alex"#]
+    );
+    assert_eq!(dispatch("give alex").unwrap(), ["[] to alex"]);
+    assert!(dispatch("give").is_err());
+}
+
+#[test]
+fn lazy_and_greedy_vec_followed_by_a_literal() {
+    assert_eq!(
+        dispatch("note lazy a b until c until d").unwrap(),
+        [r#"["a", "b"] until c until d"#]
+    );
+    assert_eq!(
+        dispatch("note greedy a b until c until d").unwrap(),
+        [r#"["a", "b", "until", "c"] until d"#]
+    );
+    assert_eq!(
+        dispatch("note lazy until everything").unwrap(),
+        ["[] until everything"]
+    );
+    assert!(dispatch("note lazy a b c").is_err());
+}
+
+#[test]
+fn trailing_optional_arguments() {
+    assert_eq!(dispatch("tp 1").unwrap(), ["1 None None"]);
+    assert_eq!(dispatch("tp 1 2").unwrap(), ["1 Some(2) None"]);
+    assert_eq!(dispatch("tp 1 2 3").unwrap(), ["1 Some(2) Some(3)"]);
+    assert!(dispatch("tp 1 x").is_err());
+    assert_eq!(tp::USAGE.usage, ["<x: i32> [<y: i32>] [<z: i32>]"]);
+}
+
+#[test]
+fn aliases_with_digits_and_underscores() {
+    let (source, _) = source();
+    assert_eq!(source.aliases("tp"), ["t", "teleport_to", "tp2"]);
+    assert_eq!(dispatch("t 1").unwrap(), ["1 None None"]);
+}
+
+#[test]
+fn greedy_string_takes_the_rest_of_the_command() {
+    assert_eq!(
+        dispatch("say general hello  \"there\"").unwrap(),
+        [r#"general: hello  "there""#]
+    );
+    assert!(dispatch("say general").is_err());
+    assert_eq!(
+        say::USAGE.usage,
+        ["<channel: Word> <message: GreedyString>"]
+    );
+}
+
+#[test]
+fn generated_parsers_can_be_called_directly() {
+    let mut parser = CommandParser::new("tp 4 5");
+    parser.advance(2);
+    let Ok(Execute::Sync(execute)) = tp::parse_variant_0(&mut parser) else {
+        panic!("expected a synchronous variant");
+    };
+    let calls = Rc::new(Calls::default());
+    assert!(matches!(execute(&calls), Ok(0)));
+    assert_eq!(calls.0.take(), ["4 Some(5) None"]);
+
+    let mut parser = CommandParser::new("note greedy x until y");
+    parser.advance(4);
+    assert!(note::parse_variant_0(&mut parser).is_err());
+    let mut parser = CommandParser::new("note greedy x until y");
+    parser.advance(4);
+    assert!(note::parse_variant_1(&mut parser).is_ok());
+}
+
+#[test]
+fn errors_list_what_any_variant_expected() {
+    assert_eq!(dispatch("take 3").unwrap(), ["3"]);
+    assert_eq!(dispatch("take all").unwrap(), ["all"]);
+
+    let (source, _) = source();
+    let Err(CommandError::Parse(error)) = source.dispatch("take x") else {
+        panic!("`take x` should fail to parse");
+    };
+    let expected = error.expected().unwrap();
+    assert_eq!(expected.offset(), 5);
+    assert_eq!(
+        expected.items(),
+        [
+            Expected::Argument("<count: u32>".to_string()),
+            Expected::Literal("all".to_string())
+        ]
+    );
+    assert_eq!(
+        error.to_string(),
+        "Bad literal: at column 6 expected `<count: u32>` or `all`"
+    );
+}
+
+#[test]
+fn dispatch_errors_compare_by_message() {
+    let (source, _) = source();
+    assert_eq!(
+        source.dispatch("fail out of cheese"),
+        Err(CommandError::dispatch("out of cheese"))
+    );
+    assert_ne!(
+        source.dispatch("fail out of cheese"),
+        Err(CommandError::dispatch("out of milk"))
+    );
+    assert_ne!(
+        source.dispatch("fail out of cheese"),
+        Err(CommandError::ShuttingDown)
+    );
+}
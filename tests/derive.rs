@@ -0,0 +1,82 @@
+use oberst::{
+    parser::{Argument, CommandParser, ParseErrorKind},
+    Argument,
+};
+
+#[derive(Debug, PartialEq, Argument)]
+enum Status {
+    Ok,
+    NotFound,
+    HTTPError,
+    IOError,
+    #[rename = "teapot"]
+    ImATeapot,
+}
+
+fn status(text: &str) -> Option<Status> {
+    CommandParser::new(text).argument().ok()
+}
+
+#[test]
+fn enum_variants_are_parsed_from_their_names_in_snake_case() {
+    assert_eq!(status("ok"), Some(Status::Ok));
+    assert_eq!(status("not_found"), Some(Status::NotFound));
+    assert_eq!(status("NOT_FOUND"), Some(Status::NotFound));
+    assert_eq!(status("http_error"), Some(Status::HTTPError));
+    assert_eq!(status("io_error"), Some(Status::IOError));
+    assert_eq!(status("notfound"), None);
+    assert_eq!(status("h_t_t_p_error"), None);
+    assert_eq!(
+        Status::suggestions(&CommandParser::new("")),
+        ["ok", "not_found", "http_error", "io_error", "teapot"]
+    );
+}
+
+#[test]
+fn renamed_enum_variants() {
+    assert_eq!(status("teapot"), Some(Status::ImATeapot));
+    assert_eq!(status("Teapot"), Some(Status::ImATeapot));
+    assert_eq!(status("im_a_teapot"), None);
+}
+
+#[derive(Debug, PartialEq, Argument)]
+struct Point {
+    x: i32,
+    y: i32,
+    z: Status,
+}
+
+#[derive(Debug, PartialEq, Argument)]
+struct Pair(u8, char);
+
+#[test]
+fn struct_fields_are_parsed_in_declaration_order() {
+    let mut parser = CommandParser::new("1 -2   ok rest");
+    assert_eq!(
+        parser.argument::<Point>().unwrap(),
+        Point {
+            x: 1,
+            y: -2,
+            z: Status::Ok,
+        }
+    );
+    assert_eq!(parser.remaining(), " rest");
+    assert_eq!(
+        CommandParser::new("7 y").argument::<Pair>().unwrap(),
+        Pair(7, 'y')
+    );
+    assert_eq!(
+        Pair::suggestions(&CommandParser::new("")),
+        u8::suggestions(&CommandParser::new(""))
+    );
+}
+
+#[test]
+fn struct_fields_have_to_be_separated_by_whitespace() {
+    let error = CommandParser::new("1,2 ok")
+        .argument::<Point>()
+        .unwrap_err();
+    assert_eq!(error.kind, ParseErrorKind::ExpectedWhitespace);
+    assert!(CommandParser::new("1 2").argument::<Point>().is_err());
+    assert!(CommandParser::new("1 2 fine").argument::<Point>().is_err());
+}
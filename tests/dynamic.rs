@@ -0,0 +1,81 @@
+use std::{any::Any, cell::RefCell, rc::Rc};
+
+use oberst::{
+    completion::SuggestionKind,
+    dynamic::DynamicSyntax,
+    parser::{CommandParser, ParseError},
+    CommandError, CommandSource,
+};
+
+/// A player name, only ever parsed through the registry.
+#[derive(Debug, PartialEq)]
+struct Player(String);
+
+fn parse_player<'a>(parser: &mut CommandParser<'a>) -> Result<Box<dyn Any>, ParseError<'a>> {
+    let name = parser.read_while(char::is_alphanumeric);
+    if name.is_empty() {
+        return Err(parser.error(oberst::parser::ParseErrorKind::BadArgument));
+    }
+    Ok(Box::new(Player(name.to_string())))
+}
+
+fn online_players(_parser: &CommandParser) -> Vec<String> {
+    vec!["alex".to_string(), "steve".to_string()]
+}
+
+/// Records the calls made by dynamic commands.
+type Calls = Rc<RefCell<Vec<String>>>;
+
+/// Create a source with a dynamic `give` command, returning it along with the calls it records.
+fn source() -> (CommandSource<Calls>, Calls) {
+    let calls = Calls::default();
+    let source = CommandSource::new(calls.clone());
+    source.arguments_mut().register("player", parse_player);
+    assert!(source
+        .arguments_mut()
+        .register_suggestions("player", online_players));
+    source.register_dynamic(
+        "give",
+        DynamicSyntax::new("<target: player> <count: u32> times").unwrap(),
+        |calls: &Calls, mut arguments| {
+            let Player(target) = arguments.take::<Player>("target").unwrap();
+            let count = arguments.get::<u32>("count").unwrap();
+            calls.borrow_mut().push(format!("{} x{}", target, count));
+            Ok(0)
+        },
+    );
+    (source, calls)
+}
+
+#[test]
+fn dynamic_commands_are_dispatched_and_completed() {
+    let (source, calls) = source();
+    source.dispatch("give alex 3 times").unwrap();
+    assert!(matches!(
+        source.dispatch("give alex three times"),
+        Err(CommandError::Parse(_))
+    ));
+    assert!(source.dispatch("give alex 3").is_err());
+    source.dispatch("give steve 1 times").unwrap();
+    assert_eq!(calls.take(), ["alex x3", "steve x1"]);
+
+    let texts = |partial| {
+        source
+            .complete(partial)
+            .into_iter()
+            .map(|suggestion| (suggestion.text, suggestion.kind))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(texts("gi"), [("give".to_string(), SuggestionKind::Command)]);
+    assert_eq!(
+        texts("give st"),
+        [
+            ("<target: player>".to_string(), SuggestionKind::Hint),
+            ("steve".to_string(), SuggestionKind::Value),
+        ]
+    );
+    assert_eq!(
+        texts("give alex 3 t"),
+        [("times".to_string(), SuggestionKind::Literal)]
+    );
+}
@@ -0,0 +1,124 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use oberst::{register_command, Collection, CommandSource, Settings};
+
+#[derive(Default)]
+struct App {
+    game: Game,
+    warps: BTreeMap<String, Warp>,
+}
+
+#[derive(Default)]
+struct Game {
+    rules: Rules,
+}
+
+#[derive(Settings)]
+#[settings(command = "rules", context = "App", path = "game.rules")]
+struct Rules {
+    /// Whether players can damage each other.
+    pvp: bool,
+    max_players: u32,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            pvp: false,
+            max_players: 8,
+        }
+    }
+}
+
+#[derive(Collection)]
+#[collection(
+    command = "warp",
+    context = "App",
+    path = "warps",
+    validate = "check_warp"
+)]
+struct Warp {
+    x: i32,
+    /// The height of the warp, which has to be above the void.
+    y: i32,
+}
+
+fn check_warp(_app: &App, _name: &str, warp: &Warp) -> Result<(), String> {
+    if warp.y < 0 {
+        Err("Warps can't be placed below the void".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// The text written to the output of a source, one entry per write.
+type Output = Rc<RefCell<Vec<String>>>;
+
+/// Create a source with the generated commands, returning it along with the context and the text written to the output.
+fn source() -> (CommandSource<App>, Rc<RefCell<App>>, Output) {
+    let app = Rc::new(RefCell::new(App::default()));
+    let context = app.clone();
+    let source =
+        CommandSource::with_context_accessor(move || Some(context.clone()), Default::default());
+    register_command!(source, rules);
+    register_command!(source, warp);
+    let output = Rc::new(RefCell::new(vec![]));
+    let written = output.clone();
+    source.set_output(move |text| written.borrow_mut().push(text.to_string()));
+    (source, app, output)
+}
+
+#[test]
+fn settings_get_set_and_list() {
+    let (source, app, output) = source();
+    source.dispatch("rules get max_players").unwrap();
+    source.dispatch_mut("rules set max_players 16").unwrap();
+    source.dispatch_mut("rules set pvp on").unwrap();
+    source.dispatch("rules list").unwrap();
+    assert_eq!(
+        output.take(),
+        ["max_players = 8", "pvp = true\nmax_players = 16"]
+    );
+    assert_eq!(app.borrow().game.rules.max_players, 16);
+
+    assert!(source.dispatch_mut("rules set max_players -1").is_err());
+    assert!(source.dispatch("rules get speed").is_err());
+    let help = rules::USAGE.help();
+    assert!(help.contains("Whether players can damage each other."));
+}
+
+#[test]
+fn collection_add_remove_list_and_info() {
+    let (source, app, output) = source();
+    source.dispatch_mut("warp add \"spawn\" 0 64").unwrap();
+    source.dispatch_mut("warp add \"mine\" 10 12").unwrap();
+    source.dispatch("warp list").unwrap();
+    source.dispatch("warp info \"mine\"").unwrap();
+    assert_eq!(output.take(), ["mine, spawn", "x = 10, y = 12"]);
+
+    assert_eq!(
+        source
+            .dispatch_mut("warp add \"mine\" 1 1")
+            .unwrap_err()
+            .to_string(),
+        "There already is a warp named `mine`"
+    );
+    assert_eq!(
+        source
+            .dispatch_mut("warp add \"pit\" 0 -5")
+            .unwrap_err()
+            .to_string(),
+        "Warps can't be placed below the void"
+    );
+
+    source.dispatch_mut("warp remove \"mine\"").unwrap();
+    assert_eq!(
+        source
+            .dispatch("warp info \"mine\"")
+            .unwrap_err()
+            .to_string(),
+        "There is no warp named `mine`"
+    );
+    assert!(source.dispatch_mut("warp remove \"mine\"").is_err());
+    assert_eq!(app.borrow().warps.keys().collect::<Vec<_>>(), ["spawn"]);
+}
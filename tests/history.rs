@@ -0,0 +1,88 @@
+use std::{cell::RefCell, rc::Rc};
+
+use oberst::{
+    arguments::GreedyString, define_command, parser::ParserConfig, register_command, Builtin,
+    CommandSource,
+};
+
+define_command! {say (()) {
+    fn say(_context: &(), message: GreedyString) {
+        oberst::write_output(&message);
+    }
+}}
+
+/// The text written to the output of a source, one entry per write.
+type Output = Rc<RefCell<Vec<String>>>;
+
+/// Create a source expanding history references, returning it along with the text written to its output.
+fn source(history_size: usize) -> (CommandSource<()>, Output) {
+    let config = ParserConfig {
+        history_expansion: true,
+        history_size,
+        ..Default::default()
+    };
+    let source = CommandSource::with_config((), config);
+    register_command!(source, say);
+    source.enable_builtin(Builtin::History);
+    let output = Output::default();
+    let written = output.clone();
+    source.set_output(move |text| written.borrow_mut().push(text.to_string()));
+    (source, output)
+}
+
+#[test]
+fn previous_and_numbered_commands_are_expanded() {
+    let (source, output) = source(10);
+    source.dispatch("say one").unwrap();
+    source.dispatch("say two").unwrap();
+    source.dispatch("!!").unwrap();
+    source.dispatch("say !1 and !2").unwrap();
+    assert_eq!(output.take(), ["one", "two", "two", "say one and say two"]);
+    assert_eq!(
+        source.history(),
+        [
+            (1, "say one".to_string()),
+            (2, "say two".to_string()),
+            (3, "say two".to_string()),
+            (4, "say say one and say two".to_string()),
+        ]
+    );
+
+    let error = source.dispatch("say !9").unwrap_err();
+    assert_eq!(error.to_string(), "no command `!9` in the history");
+}
+
+#[test]
+fn references_inside_words_quotes_or_after_a_backslash_are_kept() {
+    let (source, output) = source(10);
+    source.dispatch("say one").unwrap();
+    source.dispatch("say wow!! !x").unwrap();
+    source.dispatch(r#"say "!!" \!! \!1"#).unwrap();
+    assert_eq!(output.take(), ["one", "wow!! !x", r#""!!" !! !1"#]);
+}
+
+#[test]
+fn old_commands_are_forgotten() {
+    let (source, output) = source(2);
+    source.dispatch("say one").unwrap();
+    source.dispatch("say two").unwrap();
+    source.dispatch("say three").unwrap();
+    assert!(source.dispatch("!1").is_err());
+    source.dispatch("!2").unwrap();
+    source.dispatch("history").unwrap();
+    assert_eq!(
+        output.take(),
+        [
+            "one",
+            "two",
+            "three",
+            "two",
+            "    4  say two\n    5  history"
+        ]
+    );
+
+    let (without_history, _) = self::source(0);
+    without_history.dispatch("say one").unwrap();
+    assert!(without_history.history().is_empty());
+    assert!(without_history.dispatch("!!").is_err());
+}
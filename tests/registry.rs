@@ -0,0 +1,92 @@
+use std::any::Any;
+
+use oberst::{
+    completion::SuggestionKind,
+    define_command,
+    dynamic::ArgumentRegistry,
+    parser::{CommandParser, ParseError, ParseErrorKind},
+    register_command, CommandSource,
+};
+
+/// A player name, only ever parsed through the registry.
+#[derive(Debug, PartialEq)]
+struct Player(String);
+
+fn parse_player<'a>(parser: &mut CommandParser<'a>) -> Result<Box<dyn Any>, ParseError<'a>> {
+    let name = parser.read_while(char::is_alphanumeric);
+    if name.is_empty() {
+        return Err(parser.error(ParseErrorKind::BadArgument));
+    }
+    Ok(Box::new(Player(name.to_string())))
+}
+
+fn online_players(_parser: &CommandParser) -> Vec<String> {
+    vec!["alex".to_string(), "steve".to_string()]
+}
+
+#[test]
+fn registry_kinds_and_suggestion_providers() {
+    let mut registry = ArgumentRegistry::with_builtins();
+    assert!(registry.kinds().any(|kind| kind == "u32"));
+    assert!(!registry.register_suggestions("player", online_players));
+
+    registry.register("player", parse_player);
+    assert!(registry
+        .suggest("player", &CommandParser::new(""))
+        .is_empty());
+    assert!(registry.register_suggestions("player", online_players));
+    assert_eq!(
+        registry.suggest("player", &CommandParser::new("")),
+        ["alex", "steve"]
+    );
+
+    let parsed = registry
+        .parse("player", &mut CommandParser::new("alex"))
+        .unwrap();
+    assert_eq!(parsed.downcast_ref(), Some(&Player("alex".to_string())));
+    assert!(registry
+        .parse("team", &mut CommandParser::new("red"))
+        .is_err());
+
+    // Registering the kind again drops its suggestion provider
+    registry.register("player", parse_player);
+    assert!(registry
+        .suggest("player", &CommandParser::new(""))
+        .is_empty());
+}
+
+#[test]
+fn types_resolve_to_kinds() {
+    let registry = ArgumentRegistry::with_builtins();
+    assert_eq!(registry.kind_of("u32"), Some("u32"));
+    assert_eq!(
+        registry.kind_of(std::any::type_name::<String>()),
+        Some("String")
+    );
+    assert_eq!(registry.kind_of("Player"), None);
+}
+
+define_command! {tp (()) {
+    fn tp(_context: &(), _x: i32) {}
+}}
+
+#[test]
+fn completion_uses_registered_suggestion_providers() {
+    let source = CommandSource::new(());
+    register_command!(source, tp);
+    let values = |source: &CommandSource<()>| {
+        source
+            .complete("tp ")
+            .into_iter()
+            .filter(|suggestion| suggestion.kind == SuggestionKind::Value)
+            .map(|suggestion| suggestion.text)
+            .collect::<Vec<_>>()
+    };
+    assert!(values(&source).is_empty());
+
+    fn origin(_parser: &CommandParser) -> Vec<String> {
+        vec!["0".to_string()]
+    }
+    assert!(source.arguments_mut().register_suggestions("i32", origin));
+    assert_eq!(values(&source), ["0"]);
+}
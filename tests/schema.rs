@@ -0,0 +1,73 @@
+use oberst::schema::{CommandSchema, NodeSchema, Schema, SchemaDiff, VariantSchema};
+
+/// A command with a single variant taking a literal and an argument with the given help text.
+fn command(name: &str, help: &str) -> CommandSchema {
+    CommandSchema {
+        name: name.to_string(),
+        key: format!("commands.{}", name),
+        aliases: vec![format!("{}2", name)],
+        variants: vec![VariantSchema {
+            key: format!("commands.{}.0", name),
+            nodes: vec![
+                NodeSchema::Literal("to".to_string()),
+                NodeSchema::Argument {
+                    name: "target".to_string(),
+                    key: format!("commands.{}.target", name),
+                    ty: "Option<Player>".to_string(),
+                    kind: Some("player".to_string()),
+                    help: Some(help.to_string()),
+                    optional: true,
+                },
+            ],
+        }],
+    }
+}
+
+#[test]
+fn compact_format_round_trip() {
+    let schema = Schema::new([
+        command("give", "Who gets it: a player\tor \\ nobody"),
+        command("kick", "Ends with a carriage return\r"),
+        command("warn", "Spans\r\nlines"),
+    ]);
+    let text = schema.serialize_compact();
+    assert_eq!(text.lines().count(), 4);
+    assert_eq!(Schema::deserialize_compact(&text), Ok(schema.clone()));
+
+    let tampered = text.replace("kick", "kock");
+    assert_eq!(Schema::deserialize_compact(&tampered).unwrap_err().line, 1);
+    assert_eq!(
+        Schema::deserialize_compact(&text.replace("\tgive2", "\tgive2\\q"))
+            .unwrap_err()
+            .line,
+        2
+    );
+}
+
+#[test]
+fn checksums_diffs_and_applying_them() {
+    let previous = Schema::new([command("give", "old"), command("kick", "kick")]);
+    let current = Schema::new([command("give", "new"), command("warn", "warn")]);
+    assert_eq!(previous.checksum(), previous.clone().checksum());
+    assert_ne!(previous.checksum(), current.checksum());
+
+    let diff = current.diff(&previous);
+    assert_eq!(diff.from, previous.checksum());
+    assert_eq!(diff.to, current.checksum());
+    assert_eq!(
+        diff.changed,
+        [command("give", "new"), command("warn", "warn")]
+    );
+    assert_eq!(diff.removed, ["kick"]);
+    assert!(current.diff(&current).is_empty());
+
+    let diff = SchemaDiff::deserialize_compact(&diff.serialize_compact()).unwrap();
+    let mut updated = previous.clone();
+    updated.apply(&diff).unwrap();
+    assert_eq!(updated, current);
+
+    // The diff only applies to the snapshot it was created for
+    let mut unrelated = current.clone();
+    assert!(unrelated.apply(&diff).is_err());
+    assert_eq!(unrelated, current);
+}
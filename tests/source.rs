@@ -0,0 +1,215 @@
+use std::{cell::RefCell, rc::Rc};
+
+use oberst::{
+    arguments::GreedyString,
+    completion::Suggestion,
+    define_command,
+    dispatch::{DispatchOptions, EngineFlag},
+    parser::{ParseErrorKind, ParserConfig},
+    register_command, Builtin, CommandError, CommandResult, CommandSource,
+};
+
+/// Text written to an output or seen by a hook, one entry per call.
+type Output = Rc<RefCell<Vec<String>>>;
+
+define_command! {hello (()) {
+    fn hello(_context: &()) {
+        oberst::write_output("hello");
+    }
+}}
+
+define_command! {help (()) {
+    fn help(_context: &()) {}
+}}
+
+define_command! {say (()) {
+    fn say(_context: &(), message: GreedyString) {
+        oberst::write_output(&message);
+    }
+}}
+
+fn texts(suggestions: Vec<Suggestion>) -> Vec<String> {
+    suggestions
+        .into_iter()
+        .map(|suggestion| suggestion.text)
+        .collect()
+}
+
+fn is_unknown(result: CommandResult) -> bool {
+    matches!(result, Err(CommandError::Parse(error)) if error.kind == ParseErrorKind::UnknownCommand)
+}
+
+#[test]
+fn suggestion_cache_hits_and_invalidation() {
+    let source = CommandSource::new(());
+    register_command!(source, hello);
+    source.enable_suggestion_cache(2);
+
+    let (suggestions, stats) = source.complete_with_stats("he");
+    assert_eq!(texts(suggestions), ["hello"]);
+    assert!(!stats.cached);
+    let (suggestions, stats) = source.complete_with_stats("he");
+    assert_eq!(texts(suggestions), ["hello"]);
+    assert!(stats.cached);
+
+    register_command!(source, help);
+    let (suggestions, stats) = source.complete_with_stats("he");
+    assert_eq!(texts(suggestions), ["hello", "help"]);
+    assert!(!stats.cached);
+
+    source.complete("h");
+    assert!(source.complete_with_stats("h").1.cached);
+    source.clear_suggestion_cache();
+    assert!(!source.complete_with_stats("h").1.cached);
+    assert!(source.complete_with_stats("h").1.cached);
+    drop(source.arguments_mut());
+    assert!(!source.complete_with_stats("h").1.cached);
+}
+
+#[test]
+fn builder_applies_its_configuration() {
+    let output = Output::default();
+    let written = output.clone();
+    let events = Output::default();
+    let seen = events.clone();
+    let config = ParserConfig {
+        budget: Some(100),
+        ..Default::default()
+    };
+    let source = CommandSource::builder(())
+        .config(config)
+        .builtin(Builtin::Which)
+        .output(move |text| written.borrow_mut().push(text.to_string()))
+        .permission_check(|_, usage| usage.name != "help")
+        .fallback(|_, command| Ok(command.len() as i32))
+        .dispatch_hook(move |event| seen.borrow_mut().push(event.command.to_string()))
+        .suggestion_cache(4)
+        .build();
+    register_command!(source, hello);
+    register_command!(source, help);
+
+    assert_eq!(source.config().budget, Some(100));
+    source.dispatch("hello").unwrap();
+    assert!(matches!(
+        source.dispatch("help"),
+        Err(CommandError::PermissionDenied)
+    ));
+    assert_eq!(source.dispatch("unknown thing").unwrap(), 13);
+    source.dispatch("which hello").unwrap();
+    let output = output.take();
+    assert_eq!(output[0], "hello");
+    assert!(output[1].starts_with("hello\n  registered at"));
+    assert_eq!(
+        events.take(),
+        ["hello", "help", "unknown thing", "which hello"]
+    );
+
+    source.complete("he");
+    assert!(source.complete_with_stats("he").1.cached);
+}
+
+#[test]
+fn overlays_dont_change_the_original_commands() {
+    let source = CommandSource::new(());
+    register_command!(source, hello);
+    let overlay = source.overlay();
+    register_command!(overlay, help);
+    assert!(overlay.unregister("hello"));
+
+    assert!(overlay.dispatch("help").is_ok());
+    assert!(is_unknown(overlay.dispatch("hello")));
+    assert!(source.dispatch("hello").is_ok());
+    assert!(is_unknown(source.dispatch("help")));
+}
+
+#[test]
+fn dispatch_hooks_see_the_correlation_id_and_result() {
+    let source = CommandSource::new(());
+    register_command!(source, hello);
+    let events = Output::default();
+    let seen = events.clone();
+    source.set_dispatch_hook(move |event| {
+        seen.borrow_mut().push(format!(
+            "{} {:?} {:?}",
+            event.command, event.correlation_id, event.result
+        ))
+    });
+
+    let dispatched = source.dispatch_with("hello", DispatchOptions::correlated("message 7"));
+    assert!(matches!(dispatched.result, Ok(0)));
+    assert_eq!(dispatched.correlation_id.as_deref(), Some("message 7"));
+    assert!(source.dispatch("bye").is_err());
+    let events = events.take();
+    assert_eq!(events[0], r#"hello Some("message 7") Ok(0)"#);
+    assert!(events[1].starts_with("bye None Err("));
+}
+
+#[test]
+fn flagless_commands_ignore_the_help_suffix() {
+    let source = CommandSource::new(());
+    register_command!(source, say);
+    source.register_alias("echo", "say");
+    let output = Output::default();
+    let written = output.clone();
+    source.set_output(move |text| written.borrow_mut().push(text.to_string()));
+
+    source.dispatch("say --help").unwrap();
+    assert_ne!(output.take(), ["--help"]);
+    assert!(source.set_flagless("echo", true));
+    source.dispatch("say --help").unwrap();
+    source.dispatch("echo --help").unwrap();
+    assert_eq!(output.take(), ["--help", "--help"]);
+    assert!(!source.set_flagless("shout", true));
+}
+
+#[test]
+fn flagless_commands_keep_their_engine_flags() {
+    let config = ParserConfig {
+        engine_flags: vec![EngineFlag::switch("--silent")],
+        ..Default::default()
+    };
+    let source = CommandSource::builder(()).config(config).build();
+    register_command!(source, say);
+    source.register_alias("echo", "say");
+    let output = Output::default();
+    let written = output.clone();
+    source.set_output(move |text| written.borrow_mut().push(text.to_string()));
+
+    let dispatched = source.dispatch_with("say hi --silent", Default::default());
+    assert!(dispatched.flags.contains_key("--silent"));
+    assert_eq!(output.take(), ["hi"]);
+    assert!(source.set_flagless("say", true));
+    let dispatched = source.dispatch_with("echo hi --silent", Default::default());
+    assert!(dispatched.flags.is_empty());
+    assert_eq!(output.take(), ["hi --silent"]);
+}
+
+#[test]
+fn change_listeners_receive_the_difference() {
+    let source = CommandSource::new(());
+    let diffs = Rc::new(RefCell::new(Vec::new()));
+    let seen = diffs.clone();
+    source.on_change(move |diff| {
+        let changed = diff.changed.iter().map(|command| command.name.clone());
+        seen.borrow_mut()
+            .push((changed.collect::<Vec<_>>(), diff.removed.clone()));
+    });
+
+    register_command!(source, hello);
+    register_command!(source, help);
+    assert!(source.register_alias("hi", "hello"));
+    assert!(source.set_enabled("help", false));
+    assert!(!source.set_enabled("bye", false));
+    assert!(source.set_enabled("help", false));
+    assert!(source.unregister("hello"));
+    assert_eq!(
+        diffs.take(),
+        [
+            (vec!["hello".to_string()], vec![]),
+            (vec!["help".to_string()], vec![]),
+            (vec!["hello".to_string()], vec![]),
+            (vec![], vec!["help".to_string()]),
+            (vec![], vec!["hello".to_string()]),
+        ]
+    );
+}